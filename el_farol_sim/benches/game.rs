@@ -0,0 +1,71 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use el_farol_lib::simulation_logic::agent::Agent;
+use el_farol_lib::simulation_logic::game::{Game, DEFAULT_THRESHOLD};
+use el_farol_lib::simulation_logic::policy::{ConstantPolicy, FullHistoryAveragePolicy};
+use ndarray::Array2;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::sync::Arc;
+
+/// A 200×200 game of full-history averagers with 500 rounds of history, so that every
+/// prediction is expensive enough for the parallel pass to pay off.
+fn large_game() -> Game {
+    let grid = Array2::from_elem((200, 200), Agent::new(Arc::new(FullHistoryAveragePolicy)));
+    let mut game = Game::new(grid, DEFAULT_THRESHOLD);
+    game.history = (0..500).map(|round| (round % 10) as f64 / 10.0).collect();
+    game
+}
+
+/// A 200×200 game of constant predictors with neighbor context, where predictions are so cheap
+/// that a round's cost is dominated by the bookkeeping around them (buffers, contexts, scoring).
+fn cheap_game() -> Game {
+    let size = 200;
+    let grid = Array2::from_elem((size, size), Agent::new(Arc::new(ConstantPolicy::new(0.5))));
+    let mut game = Game::new(grid, DEFAULT_THRESHOLD);
+    let neighborhoods = (0..size * size)
+        .map(|i| vec![(i + 1) % (size * size), (i + size) % (size * size)])
+        .collect();
+    game.set_neighborhoods(Some(neighborhoods));
+    game
+}
+
+/// Compares a round on one rayon thread with one on the default pool. The gap depends on the
+/// number of cores: so far this has only been measured on a single-core machine, where both took
+/// about 14.8 ms and the parallel pass showed no speedup.
+fn game_round(c: &mut Criterion) {
+    let mut group = c.benchmark_group("game_round_200x200");
+    group.sample_size(20);
+
+    let single_thread = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+    group.bench_function("single_thread", |b| {
+        let mut game = large_game();
+        let mut rng = StdRng::seed_from_u64(0);
+        b.iter(|| {
+            single_thread.install(|| game.run(&mut rng));
+            game.history.pop();
+        })
+    });
+
+    group.bench_function("parallel", |b| {
+        let mut game = large_game();
+        let mut rng = StdRng::seed_from_u64(0);
+        b.iter(|| {
+            game.run(&mut rng);
+            game.history.pop();
+        })
+    });
+
+    group.finish();
+
+    c.bench_function("game_round_cheap_200x200", |b| {
+        let mut game = cheap_game();
+        let mut rng = StdRng::seed_from_u64(0);
+        b.iter(|| {
+            game.run(&mut rng);
+            game.history.clear();
+        })
+    });
+}
+
+criterion_group!(benches, game_round);
+criterion_main!(benches);
@@ -0,0 +1,52 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use el_farol_lib::simulation_logic::policy::{
+    ExponentialMovingAveragePolicy, GeneralizedMeanPolicy, MovingAveragePolicy, Policy,
+};
+use el_farol_lib::simulation_logic::simulation::{Simulation, SimulationConfig};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Counts heap allocations so the bench can report allocations per frame.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn policy_id_grid(c: &mut Criterion) {
+    // Policies with formatted names, which allocate a String on every `name()` call
+    let initial_strategies: Vec<Arc<dyn Policy>> = vec![
+        Arc::new(MovingAveragePolicy::<5>),
+        Arc::new(ExponentialMovingAveragePolicy::new(0.5)),
+        Arc::new(GeneralizedMeanPolicy::<5>::new(2.0)),
+    ];
+    let simulation = Simulation::new(SimulationConfig {
+        grid_size: 200,
+        initial_strategies,
+        seed: Some(0),
+        ..Default::default()
+    });
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    simulation.policy_id_grid();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("policy_id_grid on 200x200: {} allocations per frame", allocations);
+
+    c.bench_function("policy_id_grid_200x200", |b| b.iter(|| simulation.policy_id_grid()));
+}
+
+criterion_group!(benches, policy_id_grid);
+criterion_main!(benches);
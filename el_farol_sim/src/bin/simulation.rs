@@ -1,76 +1,108 @@
-use dotenvy;
 use el_farol_lib::simulation_logic::{
-    policy::{
-        AlwaysGo, ComplexFormulaPolicy, DrunkardPolicy, EvenHistoryAveragePolicy,
-        ExponentialMovingAveragePolicy, FullHistoryAveragePolicy, GeneralizedMeanPolicy,
-        MovingAveragePolicy, NeverGo, PredictFromDayBeforeYesterday, PredictFromYesterday,
-        RandomPolicy, SlidingWeightedAveragePolicy, StupidNerdPolicy, UniformPolicy,
-        WeightedHistoryPolicy,
-    },
-    simulation::{Simulation, SimulationConfig},
+    policy::{AlwaysGo, NeverGo, Policy, POLICY_TEMPLATES},
+    simulation::{InitPattern, IterationTimings, NeighborDistance, PhaseTimings, SimulationConfig},
 };
-use el_farol_lib::{SerializableSimulationConfig, SimulationData};
+use el_farol_lib::simulation_logic::metrics::attendance_autocorr;
+use clap::Parser;
+use el_farol_lib::frame_stream::FrameStreamWriter;
+use el_farol_lib::{run_simulation_with_callback, ElFarolError, SerializableSimulationConfig, SimulationData};
 use indicatif::{ProgressBar, ProgressStyle};
-use liblzma::write::XzEncoder;
 use std::error::Error;
 use std::fs;
-use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
-use chrono;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Print the names of all built-in policies with their parameter syntax and exit
+    #[arg(long)]
+    list_policies: bool,
+    /// Print mean and max wall-clock time of the game rounds and the adaptation step
+    #[arg(long)]
+    timings: bool,
+    /// Hide the progress bar and the run summary, and only log warnings and errors (set RUST_LOG
+    /// to override)
+    #[arg(long)]
+    quiet: bool,
+    /// TOML file listing values per parameter (e.g. `temperature = [0.1, 1.0]`); runs and saves
+    /// every combination of them
+    #[arg(long)]
+    sweep: Option<PathBuf>,
+    /// Also write every frame to <STREAM>/<name>/ as it is produced, for `visualizer --follow`
+    #[arg(long)]
+    stream: Option<PathBuf>,
+    /// Re-run the config saved in this .bin.xz file with its seed and check that every frame
+    /// matches the saved one
+    #[arg(long)]
+    replay: Option<PathBuf>,
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     dotenvy::dotenv().ok();
-    // Initialize logging
-    env_logger::init();
+    let args = Args::parse();
+    // Initialize logging; status messages go to stderr so stdout stays clean for scripts
+    let default_level = if args.quiet { "warn" } else { "info" };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_level)).init();
+
+    if args.list_policies {
+        for template in POLICY_TEMPLATES {
+            println!("{:<40} e.g. {}", template.syntax(), template.example_name());
+        }
+        return Ok(());
+    }
 
-    let initial_strategies: Vec<Arc<dyn el_farol_lib::simulation_logic::policy::Policy>> = vec![
+    if let Some(path) = &args.replay {
+        replay(path)?;
+        println!("{}: replay matches all saved frames", path.display());
+        return Ok(());
+    }
+
+    let initial_strategies: Vec<Arc<dyn Policy>> = vec![
         Arc::new(AlwaysGo),
         Arc::new(NeverGo),
-    //     Arc::new(PredictFromYesterday),
-    //     Arc::new(PredictFromDayBeforeYesterday),
-    //     Arc::new(RandomPolicy),
-    //     Arc::new(MovingAveragePolicy::<3>),
-    //     Arc::new(MovingAveragePolicy::<5>),
-    //     Arc::new(MovingAveragePolicy::<10>),
-    //     Arc::new(FullHistoryAveragePolicy),
-    //     Arc::new(EvenHistoryAveragePolicy),
-    //     Arc::new(ComplexFormulaPolicy),
-    //     Arc::new(DrunkardPolicy),
-    //     Arc::new(StupidNerdPolicy),
-    //  //   Arc::new(UniformPolicy::new(0.0, 1.0)),
-    // //    //Arc::new(UniformPolicy::new(0.25, 0.75)),
-    // //    // Arc::new(UniformPolicy::new(0.4, 0.6)),
-    // //    // Arc::new(WeightedHistoryPolicy::new()),
-    // //     // Arc::new(SlidingWeightedAveragePolicy::new()),
-    // //     // Arc::new(ExponentialMovingAveragePolicy::new(0.1)),
-    // //     // Arc::new(ExponentialMovingAveragePolicy::new(0.5)),
-    // //     // Arc::new(ExponentialMovingAveragePolicy::new(0.9)),
-    //     Arc::new(GeneralizedMeanPolicy::<5>::new(1.0)), // Arithmetic mean
-    //     Arc::new(GeneralizedMeanPolicy::<5>::new(2.0)), // Quadratic mean
-    //     Arc::new(GeneralizedMeanPolicy::<5>::new(-1.0)), // Harmonic mean
     ];
-    let strategy_names: Vec<String> = initial_strategies.iter().map(|p| p.name()).collect();
 
     // Create simulation configuration
     let config = SimulationConfig {
         name: "smooth_and_everything_back_to_two".to_string(),
         description: "We only have the always go and never go strategy and no randomization".to_string(),
         grid_size: 100,
-        neighbor_distance: 1,
+        neighbor_distance: NeighborDistance::new(1),
         temperature: 1.0,
         policy_retention_rate: 0.9,
         num_iterations: 2000,
         rounds_per_update: 5,
         initial_strategies,
-        start_random: true,
+        init_pattern: InitPattern::Random,
+        threshold: 0.6,
+        record_every_round: true,
+        record_initial_frame: true,
+        ..Default::default()
+    };
+
+    let configs = match &args.sweep {
+        Some(sweep_path) => expand_sweep(&config, &parse_sweep(&fs::read_to_string(sweep_path)?)?)?,
+        None => vec![config],
     };
 
+    for config in configs {
+        run_and_save(config, &args)?;
+    }
+
+    Ok(())
+}
+
+/// Runs one simulation and writes it to `$EL_FARO_HOME/simulations/<name>_<timestamp>.bin.xz`.
+fn run_and_save(config: SimulationConfig, args: &Args) -> Result<(), Box<dyn Error>> {
     let num_iterations = config.num_iterations;
 
     // Create and run simulation
-    let pb = ProgressBar::new(num_iterations as u64);
+    let pb = if args.quiet {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(num_iterations as u64)
+    };
     pb.set_style(
         ProgressStyle::default_bar()
             .template(
@@ -79,39 +111,50 @@ fn main() -> Result<(), Box<dyn Error>> {
             .progress_chars("#>-"),
     );
 
-    let mut simulation = Simulation::new(config.clone());
-
-    let mut data_vec = vec![];
+    let mut stream = match &args.stream {
+        Some(stream_dir) => {
+            let dir = stream_dir.join(&config.name);
+            log::info!("Streaming frames to {}", dir.display());
+            Some(FrameStreamWriter::create(&dir, &SerializableSimulationConfig::from(&config))?)
+        }
+        None => None,
+    };
 
-    for _ in 0..num_iterations {
-        data_vec.push(simulation.run_iteration());
+    let mut timings = IterationTimings::default();
+    let simulation_data = run_simulation_with_callback(config, |simulation, frame| {
         pb.inc(1);
-    }
+        timings = simulation.timings().clone();
+        if let Some(writer) = stream.as_mut() {
+            // A failed write only affects the live view, the full run is still saved below
+            if let Err(e) = writer.write_frame(frame) {
+                log::warn!("Failed to stream frame: {}", e);
+            }
+        }
+    })?;
     pb.finish_with_message("simulation complete");
+    if let Some(writer) = stream {
+        writer.finish()?;
+    }
 
-    let serializable_config = SerializableSimulationConfig {
-        name: config.name.clone(),
-        description: config.description,
-        grid_size: config.grid_size,
-        neighbor_distance: config.neighbor_distance,
-        temperature: config.temperature,
-        policy_retention_rate: config.policy_retention_rate,
-        num_iterations: config.num_iterations,
-        rounds_per_update: config.rounds_per_update,
-        initial_strategies: strategy_names.clone(),
-        start_random: config.start_random,
-    };
-
-    let simulation_data = SimulationData {
-        config: serializable_config,
-        frames: data_vec,
-    };
+    if args.timings {
+        print_timing_summary("game rounds", &timings.game_rounds);
+        print_timing_summary("adaptation", &timings.adaptation);
+    }
 
-    // Serialize and save simulation data
-    let encoded = bincode::serialize(&simulation_data)?;
+    if !args.quiet {
+        println!("{}: {}", simulation_data.config.name, simulation_data.summary());
+        // Negative lag-1 autocorrelation indicates alternating attendance
+        let attendance: Vec<f64> = simulation_data.settled_frames().iter().map(|frame| frame.attendance_ratio).collect();
+        println!(
+            "{}: attendance autocorrelation lag 1 {:.3}, lag 2 {:.3}",
+            simulation_data.config.name,
+            attendance_autocorr(&attendance, 1),
+            attendance_autocorr(&attendance, 2),
+        );
+    }
 
     let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
-    let filename = format!("{}_{}.bin.xz", config.name, timestamp);
+    let filename = format!("{}_{}.bin.xz", simulation_data.config.name, timestamp);
 
     let mut output_path = PathBuf::new();
     if let Ok(val) = std::env::var("EL_FARO_HOME") {
@@ -121,16 +164,194 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     fs::create_dir_all(&output_path)?;
     output_path.push(filename);
+    simulation_data.save(&output_path)?;
 
-    let file = File::create(&output_path)?;
-    let mut encoder = XzEncoder::new_parallel(file, 6);
-    encoder.write_all(&encoded)?;
-    encoder.finish()?;
-
-    println!(
+    log::info!(
         "Simulation data successfully compressed to {}",
         output_path.display()
     );
 
     Ok(())
 }
+
+/// Re-runs the saved run at `path` from its config and seed and returns an error naming the first
+/// frame that differs from the saved one.
+fn replay(path: &Path) -> Result<(), Box<dyn Error>> {
+    let saved = SimulationData::load(path)?;
+    if saved.config.seed.is_none() {
+        return Err(ElFarolError::Config("the saved run has no seed and cannot be replayed".to_string()).into());
+    }
+    let replayed = run_simulation_with_callback(saved.config.to_simulation_config()?, |_, _| {})?;
+    // Frames have no PartialEq; their encodings are equal iff all fields are
+    for (index, (saved_frame, replayed_frame)) in saved.frames.iter().zip(&replayed.frames).enumerate() {
+        if bincode::serialize(saved_frame)? != bincode::serialize(replayed_frame)? {
+            return Err(format!("frame {} differs from the saved run", index).into());
+        }
+    }
+    if saved.frames.len() != replayed.frames.len() {
+        return Err(format!(
+            "replay produced {} frames, the saved run has {}",
+            replayed.frames.len(),
+            saved.frames.len()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// A swept parameter and the values it takes.
+type SweepAxis = (String, Vec<f64>);
+
+/// Parses a sweep file mapping parameter names to lists of values, e.g.
+///
+/// ```toml
+/// temperature = [0.1, 0.5, 1.0]
+/// policy_retention_rate = [0.2, 0.8]
+/// ```
+fn parse_sweep(sweep: &str) -> Result<Vec<SweepAxis>, Box<dyn Error>> {
+    let table: toml::Table = toml::from_str(sweep)?;
+    table
+        .into_iter()
+        .map(|(name, values)| {
+            let values = values
+                .as_array()
+                .ok_or_else(|| format!("sweep parameter {} must be a list of numbers", name))?
+                .iter()
+                .map(|value| {
+                    value
+                        .as_float()
+                        .or_else(|| value.as_integer().map(|v| v as f64))
+                        .ok_or_else(|| format!("sweep parameter {} must be a list of numbers", name))
+                })
+                .collect::<Result<Vec<f64>, _>>()?;
+            Ok((name, values))
+        })
+        .collect()
+}
+
+/// Returns one config per point of the cartesian product of `axes`, named after `base.name` and
+/// the parameter values, e.g. `base_temperature=0.5_policy_retention_rate=0.2`.
+fn expand_sweep(base: &SimulationConfig, axes: &[SweepAxis]) -> Result<Vec<SimulationConfig>, Box<dyn Error>> {
+    let mut configs = vec![base.clone()];
+    for (name, values) in axes {
+        let mut expanded = Vec::with_capacity(configs.len() * values.len());
+        for config in &configs {
+            for &value in values {
+                let mut config = config.clone();
+                set_sweep_parameter(&mut config, name, value)?;
+                config.validate()?;
+                config.name = format!("{}_{}={}", config.name, name, value);
+                expanded.push(config);
+            }
+        }
+        configs = expanded;
+    }
+    Ok(configs)
+}
+
+fn set_sweep_parameter(config: &mut SimulationConfig, name: &str, value: f64) -> Result<(), Box<dyn Error>> {
+    let as_count = || {
+        (value >= 0.0 && value.fract() == 0.0)
+            .then_some(value as usize)
+            .ok_or_else(|| format!("sweep parameter {} must be a non-negative integer, got {}", name, value))
+    };
+    match name {
+        "temperature" => config.temperature = value,
+        "policy_retention_rate" => config.policy_retention_rate = value,
+        "threshold" => config.threshold = value,
+        "performance_discount" => config.performance_discount = value,
+        "perception_noise" => config.perception_noise = value,
+        "softness" => config.softness = value,
+        "grid_size" => config.grid_size = as_count()?,
+        "neighbor_distance" => config.neighbor_distance = NeighborDistance::new(as_count()?),
+        "num_iterations" => config.num_iterations = as_count()?,
+        "rounds_per_update" => config.rounds_per_update = as_count()?,
+        _ => return Err(format!("unknown sweep parameter {}", name).into()),
+    }
+    Ok(())
+}
+
+fn print_timing_summary(phase: &str, timings: &PhaseTimings) {
+    if timings.count == 0 {
+        return;
+    }
+    println!(
+        "{:<12} mean {:>10.3?}  max {:>10.3?}  total {:>10.3?}",
+        phase,
+        timings.mean(),
+        timings.max,
+        timings.total
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use el_farol_lib::run_simulation;
+    use el_farol_lib::simulation_logic::policy::{ConstantPolicy, MovingAveragePolicy, PredictFromYesterday};
+
+    #[test]
+    fn test_args_parse_quiet() {
+        assert!(Args::try_parse_from(["simulation", "--quiet"]).unwrap().quiet);
+        assert!(!Args::try_parse_from(["simulation"]).unwrap().quiet);
+    }
+
+    #[test]
+    fn test_sweep_expands_cartesian_product() {
+        let axes = parse_sweep("policy_retention_rate = [0.2, 0.8]\ntemperature = [0.1, 0.5, 1]\n").unwrap();
+        let base = SimulationConfig { name: "base".to_string(), ..Default::default() };
+        let configs = expand_sweep(&base, &axes).unwrap();
+        assert_eq!(configs.len(), 6);
+        assert_eq!(configs[0].name, "base_policy_retention_rate=0.2_temperature=0.1");
+        assert_eq!(configs[5].policy_retention_rate, 0.8);
+        assert_eq!(configs[5].temperature, 1.0);
+
+        assert!(expand_sweep(&base, &parse_sweep("grid_size = [2.5]").unwrap()).is_err());
+        assert!(expand_sweep(&base, &parse_sweep("unknown = [1]").unwrap()).is_err());
+        assert!(parse_sweep("temperature = 1.0").is_err());
+    }
+
+    #[test]
+    fn test_save_then_replay() {
+        let config = SimulationConfig {
+            name: "replay".to_string(),
+            grid_size: 5,
+            num_iterations: 6,
+            rounds_per_update: 2,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo), Arc::new(ConstantPolicy::new(0.4))],
+            record_every_round: true,
+            perception_noise: 0.1,
+            seed: Some(11),
+            ..Default::default()
+        };
+        let mut data = run_simulation(config);
+        let path = std::env::temp_dir().join(format!("el_farol_replay_{}.bin.xz", std::process::id()));
+        data.save(&path).unwrap();
+        replay(&path).unwrap();
+
+        data.frames[3].attendance_ratio += 0.1;
+        data.save(&path).unwrap();
+        assert_eq!(replay(&path).unwrap_err().to_string(), "frame 3 differs from the saved run");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_history_based_policies() {
+        // These guess on the empty history of the first round
+        let config = SimulationConfig {
+            name: "replay_history".to_string(),
+            grid_size: 30,
+            num_iterations: 4,
+            rounds_per_update: 2,
+            initial_strategies: vec![Arc::new(PredictFromYesterday), Arc::new(MovingAveragePolicy::<3>)],
+            record_every_round: true,
+            seed: Some(3),
+            ..Default::default()
+        };
+        let data = run_simulation(config);
+        let path = std::env::temp_dir().join(format!("el_farol_replay_history_{}.bin.xz", std::process::id()));
+        data.save(&path).unwrap();
+        replay(&path).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,41 +1,320 @@
-use chrono::prelude::*;
 use clap::Parser;
-use el_farol_lib::{Frame, SimulationData};
+use el_farol_lib::frame_stream::FrameStreamReader;
+use el_farol_lib::simulation_logic::simulation::SimulationConfig;
+use el_farol_lib::{log_spaced, temperature_sweep, ElFarolError, Frame, SerializableSimulationConfig, SimulationData, StrategyId};
 use image::{Rgb, RgbImage};
-use imageproc::drawing::draw_filled_rect_mut;
+use imageproc::drawing::{draw_filled_rect_mut, draw_hollow_rect_mut};
 use imageproc::rect::Rect;
 use indicatif::{ProgressBar, ProgressStyle};
+use plotters::coord::Shift;
 use plotters::prelude::*;
+use serde::Serialize;
+use ndarray::{s, Array2};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs;
-use std::fs::File;
-use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use liblzma::read::XzDecoder;
-use toml;
-use dotenvy;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Path to the simulation data file
+    /// Path to the simulation data file, or to a stream directory with `--follow`
     input_file: PathBuf,
+    /// Render grid states from a stream directory written by `simulation --stream` as new frames
+    /// appear, until the run finishes
+    #[arg(long)]
+    follow: bool,
     /// Flag to enable video creation
     #[arg(long)]
     video: bool,
+    /// Additionally render attendance, strategy plots and the final grid into one dashboard.png
+    #[arg(long)]
+    dashboard: bool,
+    /// Write the per-iteration statistics to statistics.arrow (requires the `arrow` feature)
+    #[arg(long)]
+    arrow: bool,
+    /// Hide the progress bar and only log warnings and errors (set RUST_LOG to override)
+    #[arg(long)]
+    quiet: bool,
+    /// Mark cells whose policy changed since the previous frame with a black dot
+    #[arg(long)]
+    show_changes: bool,
+    /// Draw a thin gray border around every grid cell so single agents stay distinguishable
+    #[arg(long)]
+    grid_lines: bool,
+    /// Quick preview: render only every Nth frame and pool each NxN block of cells into its
+    /// majority policy
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    downsample: u32,
+    /// Also render every frame's prediction errors |prediction - attendance| into error_maps/
+    #[arg(long)]
+    error_map: bool,
+    /// Skip the per-frame grid states and only render the last frame to final_state.png; the
+    /// statistics plots are still written
+    #[arg(long, conflicts_with_all = ["video", "error_map", "prediction_map"])]
+    final_only: bool,
+    /// Also render every frame's raw predictions on a blue (0) to red (1) scale into
+    /// prediction_maps/
+    #[arg(long)]
+    prediction_map: bool,
+    /// File name of the rendered grid states; `{}` or a zero-padded `{:0N}` is replaced by the
+    /// frame index, e.g. `frame_{:06}.png` for runs with more than 9999 frames
+    #[arg(long, default_value = "state_{:04}.png", value_parser = FramePattern::parse)]
+    frame_pattern: FramePattern,
+    /// Colormap of the error maps
+    #[arg(long, value_enum, default_value_t = Colormap::Viridis)]
+    error_colormap: Colormap,
+    /// Re-run the run's config for a few iterations at log-spaced temperatures and plot the
+    /// attendance std of every run to temperature_sweep.png
+    #[arg(long)]
+    temperature_sweep: bool,
+    /// Instead of rendering, write the policy ids and predictions of frame N as CSV grids to
+    /// frame_N_policy_ids.csv and frame_N_predictions.csv; the ids can be loaded again as an
+    /// initial grid
+    #[arg(long, value_name = "N", conflicts_with = "follow")]
+    dump_frame: Option<usize>,
+    /// Number of strategies colored from the fixed base palette (at most 17); strategies past it
+    /// get generated hues
+    #[arg(long, default_value_t = BASE_STRATEGY_COLORS.len())]
+    base_colors: usize,
+    /// Multiply the size of all plot canvases, margins and fonts, e.g. 2 for high-resolution figures
+    #[arg(long, default_value_t = 1.0, value_parser = parse_scale)]
+    scale: f64,
+}
+
+/// File name pattern with exactly one frame index placeholder, see `--frame-pattern`.
+#[derive(Debug, Clone, PartialEq)]
+struct FramePattern {
+    prefix: String,
+    /// Minimum number of digits, padded with zeros; 0 for no padding
+    width: usize,
+    suffix: String,
+}
+
+impl FramePattern {
+    fn parse(pattern: &str) -> Result<Self, String> {
+        if pattern.matches('{').count() != 1 || pattern.matches('}').count() != 1 {
+            return Err(format!("frame pattern must contain exactly one index placeholder, got {}", pattern));
+        }
+        let (prefix, rest) = pattern.split_once('{').unwrap_or_default();
+        let (spec, suffix) = rest
+            .split_once('}')
+            .ok_or_else(|| format!("frame pattern has no closing brace: {}", pattern))?;
+        let width = match spec {
+            "" => 0,
+            _ => spec
+                .strip_prefix(":0")
+                .and_then(|digits| digits.parse().ok())
+                .ok_or_else(|| format!("index placeholder must be {{}} or {{:0N}}, got {{{}}}", spec))?,
+        };
+        if prefix.contains('/') || suffix.contains('/') {
+            return Err(format!("frame pattern must be a file name, got {}", pattern));
+        }
+        Ok(Self {
+            prefix: prefix.to_string(),
+            width,
+            suffix: suffix.to_string(),
+        })
+    }
+
+    fn file_name(&self, index: usize) -> String {
+        format!("{}{:0width$}{}", self.prefix, index, self.suffix, width = self.width)
+    }
+
+    /// The same pattern in ffmpeg's image sequence syntax.
+    fn ffmpeg_pattern(&self) -> String {
+        let escape = |part: &str| part.replace('%', "%%");
+        let index = match self.width {
+            0 => "%d".to_string(),
+            width => format!("%0{}d", width),
+        };
+        format!("{}{}{}", escape(&self.prefix), index, escape(&self.suffix))
+    }
+}
+
+impl Default for FramePattern {
+    fn default() -> Self {
+        Self::parse("state_{:04}.png").unwrap()
+    }
+}
+
+fn parse_scale(scale: &str) -> Result<f64, String> {
+    match scale.parse::<f64>() {
+        Ok(scale) if scale > 0.0 && scale.is_finite() => Ok(scale),
+        _ => Err(format!("scale must be a positive number, got {}", scale)),
+    }
+}
+
+/// Multiplies plot canvas sizes, margins and font sizes so figures stay legible at any resolution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PlotScale(f64);
+
+impl PlotScale {
+    /// Scales a length in pixels.
+    fn px(self, base: u32) -> u32 {
+        (base as f64 * self.0).round() as u32
+    }
+
+    /// Scales a pixel coordinate or offset.
+    fn offset(self, base: i32) -> i32 {
+        (base as f64 * self.0).round() as i32
+    }
+
+    fn font(self, base: f64) -> f64 {
+        base * self.0
+    }
+
+    /// Canvas size passed to `BitMapBackend`.
+    fn canvas(self, (width, height): (u32, u32)) -> (u32, u32) {
+        (self.px(width), self.px(height))
+    }
+}
+
+/// Strategy colors shared by the grid renders and all plots, see [`color_for_strategy`]. Holds
+/// the number of base palette colors in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct StrategyPalette(usize);
+
+impl Default for StrategyPalette {
+    fn default() -> Self {
+        Self(BASE_STRATEGY_COLORS.len())
+    }
+}
+
+impl StrategyPalette {
+    fn color(self, id: usize) -> Rgb<u8> {
+        color_for_strategy(id, self.0)
+    }
+
+    /// [`StrategyPalette::color`] for plotters.
+    fn plot_color(self, id: usize) -> RGBColor {
+        let Rgb([r, g, b]) = self.color(id);
+        RGBColor(r, g, b)
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     dotenvy::dotenv().ok();
     let args = Args::parse();
-    let file = File::open(&args.input_file)?;
-    let mut decompressor = XzDecoder::new(file);
-    let mut decoded = Vec::new();
-    decompressor.read_to_end(&mut decoded)?;
-    let simulation_data: SimulationData = bincode::deserialize(&decoded)?;
+    // Status messages go to stderr so stdout stays clean for scripts
+    let default_level = if args.quiet { "warn" } else { "info" };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_level)).init();
+
+    if args.follow {
+        return follow_stream(&args);
+    }
+
+    let simulation_data = SimulationData::load(&args.input_file)?;
+    log_run_metadata(&simulation_data.config);
+
+    let experiment_dir = experiment_dir(&simulation_data.config.name);
+    fs::create_dir_all(&experiment_dir)?;
+
+    if let Some(index) = args.dump_frame {
+        let mut manifest = Manifest::new(&experiment_dir);
+        dump_frame(&simulation_data, index, &experiment_dir, &mut manifest)?;
+        manifest.write()?;
+        log::info!("Frame {} written to: {}", index, experiment_dir.display());
+        return Ok(());
+    }
+
+    let grid_states_dir = experiment_dir.join("grid_states");
+    if !args.final_only {
+        fs::create_dir_all(&grid_states_dir)?;
+    }
+
+    let mut manifest = Manifest::new(&experiment_dir);
+    visualize_simulation(
+        &simulation_data,
+        &grid_states_dir.to_string_lossy(),
+        &experiment_dir.to_string_lossy(),
+        !args.quiet,
+        GridOptions {
+            show_changes: args.show_changes,
+            grid_lines: args.grid_lines,
+            downsample: args.downsample as usize,
+            error_colormap: args.error_map.then_some(args.error_colormap),
+            prediction_map: args.prediction_map,
+            final_only: args.final_only,
+            frame_pattern: args.frame_pattern.clone(),
+            palette: StrategyPalette(args.base_colors),
+        },
+        PlotScale(args.scale),
+        &mut manifest,
+    )?;
+
+    if args.dashboard {
+        let path = plot_dashboard(
+            &simulation_data,
+            &experiment_dir.to_string_lossy(),
+            PlotScale(args.scale),
+            StrategyPalette(args.base_colors),
+        )?;
+        manifest.record(&path, ArtifactKind::Plot);
+    }
+
+    if args.arrow {
+        #[cfg(feature = "arrow")]
+        {
+            let path = experiment_dir.join("statistics.arrow");
+            write_statistics_arrow(&simulation_data, &path)?;
+            manifest.record(&path, ArtifactKind::Statistics);
+        }
+        #[cfg(not(feature = "arrow"))]
+        return Err(ElFarolError::Config("--arrow requires the visualizer to be built with `--features arrow`".to_string()).into());
+    }
 
+    if args.temperature_sweep {
+        let temperatures = log_spaced(SWEEP_TEMPERATURE_RANGE.0, SWEEP_TEMPERATURE_RANGE.1, SWEEP_POINTS);
+        log::info!("Running temperature sweep over {} temperatures", temperatures.len());
+        let points = temperature_sweep(&sweep_config(&simulation_data.config)?, &temperatures);
+        let path = plot_temperature_sweep(&points, &experiment_dir.to_string_lossy(), PlotScale(args.scale))?;
+        manifest.record(&path, ArtifactKind::Plot);
+    }
+
+    let description_path = experiment_dir.join("description.txt");
+    fs::write(&description_path, &simulation_data.config.description)?;
+    manifest.record(&description_path, ArtifactKind::Description);
+    let attribution_path = experiment_dir.join("attendance_attribution.csv");
+    write_attendance_attribution(&simulation_data, &attribution_path)?;
+    manifest.record(&attribution_path, ArtifactKind::Table);
+
+    let sim_conf_path = experiment_dir.join("sim.conf");
+    match toml::to_string_pretty(&simulation_data.config) {
+        Ok(conf_str) => match fs::write(&sim_conf_path, conf_str) {
+            Ok(()) => manifest.record(&sim_conf_path, ArtifactKind::Config),
+            Err(e) => log::error!("Failed to write sim.conf to {}: {}", sim_conf_path.display(), e),
+        },
+        Err(e) => {
+            log::error!("Failed to serialize simulation config to TOML: {}", e);
+        }
+    }
+
+    // Last, so a failing ffmpeg leaves everything else written
+    if args.video {
+        let video_path = experiment_dir.join("simulation.mp4");
+        let written = create_video(&grid_states_dir.to_string_lossy(), &args.frame_pattern, &video_path.to_string_lossy());
+        // Index everything written so far even if ffmpeg failed
+        if written.is_ok() {
+            manifest.record(&video_path, ArtifactKind::Video);
+        }
+        manifest.write()?;
+        written?;
+    } else {
+        manifest.write()?;
+    }
+
+    log::info!("Experiment data saved to: {}", experiment_dir.display());
+
+    Ok(())
+}
+
+/// Output directory of the run called `name`: `$EL_FARO_HOME/visualisation/<name>`, or
+/// `output/<name>` if `EL_FARO_HOME` is not set.
+fn experiment_dir(name: &str) -> PathBuf {
     let mut base_output_dir = PathBuf::new();
     if let Ok(val) = std::env::var("EL_FARO_HOME") {
         base_output_dir.push(val);
@@ -43,48 +322,179 @@ fn main() -> Result<(), Box<dyn Error>> {
     } else {
         base_output_dir.push("output");
     }
+    base_output_dir.join(name)
+}
 
-    let folder_name = &simulation_data.config.name;
-    let experiment_dir = base_output_dir.join(folder_name);
-    fs::create_dir_all(&experiment_dir)?;
+/// What a file listed in the manifest contains.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ArtifactKind {
+    Plot,
+    GridState,
+    ErrorMap,
+    PredictionMap,
+    /// Only written with the `arrow` feature
+    #[cfg_attr(not(feature = "arrow"), allow(dead_code))]
+    Statistics,
+    Table,
+    Description,
+    Config,
+    Video,
+}
 
-    let grid_states_dir = experiment_dir.join("grid_states");
-    fs::create_dir_all(&grid_states_dir)?;
+#[derive(Debug, Serialize)]
+struct ManifestEntry {
+    path: String,
+    kind: ArtifactKind,
+}
 
-    visualize_simulation(&simulation_data, &grid_states_dir.to_string_lossy(), &experiment_dir.to_string_lossy())?;
+/// Index of the files written for one run, saved as manifest.json in the experiment directory so
+/// tools can find the outputs without knowing their names. Paths are relative to that directory.
+#[derive(Debug, Serialize)]
+struct Manifest {
+    #[serde(skip)]
+    root: PathBuf,
+    files: Vec<ManifestEntry>,
+}
 
-    if args.video {
-        let video_path = experiment_dir.join("simulation.mp4");
-        create_video(&grid_states_dir.to_string_lossy(), &video_path.to_string_lossy())?;
+impl Manifest {
+    fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            files: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, path: &Path, kind: ArtifactKind) {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        self.files.push(ManifestEntry {
+            path: relative.to_string_lossy().into_owned(),
+            kind,
+        });
     }
 
-    fs::write(experiment_dir.join("description.txt"), &simulation_data.config.description)?;
+    fn write(&self) -> Result<(), Box<dyn Error>> {
+        fs::write(self.root.join("manifest.json"), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
 
-    let sim_conf_path = experiment_dir.join("sim.conf");
-    match toml::to_string_pretty(&simulation_data.config) {
-        Ok(conf_str) => {
-            if let Err(e) = fs::write(&sim_conf_path, conf_str) {
-                eprintln!("Failed to write sim.conf to {}: {}", sim_conf_path.display(), e);
-            }
+/// How often `--follow` checks the stream directory for new frames.
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Renders the grid state of every frame of a stream directory as soon as it appears.
+fn follow_stream(args: &Args) -> Result<(), Box<dyn Error>> {
+    let mut reader = FrameStreamReader::open(&args.input_file);
+    let config = loop {
+        if let Some(config) = reader.config()? {
+            break config;
         }
-        Err(e) => {
-            eprintln!("Failed to serialize simulation config to TOML: {}", e);
+        std::thread::sleep(FOLLOW_POLL_INTERVAL);
+    };
+    log_run_metadata(&config);
+
+    let grid_states_dir = experiment_dir(&config.name).join("grid_states");
+    fs::create_dir_all(&grid_states_dir)?;
+    log::info!("Following {}, rendering to {}", args.input_file.display(), grid_states_dir.display());
+
+    let strategies = config.strategy_names();
+    let mut previous: Option<Frame> = None;
+    loop {
+        // Check before reading so frames written right before the marker are not missed
+        let finished = reader.is_finished();
+        let frames = reader.read_new_frames()?;
+        let first_index = reader.frames_read() - frames.len();
+        for (offset, frame) in frames.into_iter().enumerate() {
+            visualize_grid_state(
+                &frame,
+                previous.as_ref().filter(|_| args.show_changes),
+                first_index + offset,
+                &strategies,
+                StrategyPalette(args.base_colors),
+                &grid_states_dir.join(args.frame_pattern.file_name(first_index + offset)),
+                args.grid_lines,
+            )?;
+            previous = Some(frame);
+        }
+        if finished {
+            break;
         }
+        std::thread::sleep(FOLLOW_POLL_INTERVAL);
     }
 
-    println!("Experiment data saved to: {}", experiment_dir.display());
+    log::info!("Stream finished after {} frames", reader.frames_read());
+    Ok(())
+}
+
+/// Logs how the loaded run was produced, so it can be reproduced.
+fn log_run_metadata(config: &SerializableSimulationConfig) {
+    log::info!("Run: {}", config.name);
+    match config.seed {
+        Some(seed) => log::info!("  seed:       {}", seed),
+        None => log::info!("  seed:       unknown"),
+    }
+    log::info!("  version:    {} (data format {})", config.version, config.format_version);
+    log::info!("  created at: {}", config.created_at);
+}
 
+/// Name of strategy `id`, or "Strategy {id}" for ids beyond the config's strategy list (e.g.
+/// from a policy the config does not name).
+fn strategy_name(strategies: &[String], id: usize) -> String {
+    strategies.get(id).cloned().unwrap_or_else(|| format!("Strategy {}", id))
+}
+
+/// Writes each strategy's share of the total attendance as `strategy,attendance_share` rows.
+fn write_attendance_attribution(simulation_data: &SimulationData, path: &Path) -> Result<(), Box<dyn Error>> {
+    let strategies = &simulation_data.config.strategy_names();
+    let mut csv = String::from("strategy,attendance_share\n");
+    for (id, share) in simulation_data.attendance_attribution().into_iter().enumerate() {
+        let name = strategy_name(strategies, id);
+        csv.push_str(&format!("\"{}\",{}\n", name.replace('"', "\"\""), share));
+    }
+    fs::write(path, csv)?;
+    Ok(())
+}
+
+/// Writes frame `index` as two CSV grids, one row per line, see `--dump-frame`.
+fn dump_frame(
+    simulation_data: &SimulationData,
+    index: usize,
+    dir: &Path,
+    manifest: &mut Manifest,
+) -> Result<(), Box<dyn Error>> {
+    let frame = simulation_data.frames.get(index).ok_or_else(|| {
+        ElFarolError::Config(format!(
+            "frame {} out of range, the run has {} frames",
+            index,
+            simulation_data.frames.len()
+        ))
+    })?;
+    for (name, csv) in [
+        ("policy_ids", grid_csv(&frame.policy_ids)),
+        ("predictions", grid_csv(&frame.predictions)),
+    ] {
+        let path = dir.join(format!("frame_{}_{}.csv", index, name));
+        fs::write(&path, csv)?;
+        manifest.record(&path, ArtifactKind::Table);
+    }
     Ok(())
 }
 
-fn create_video(frames_dir: &str, output_path: &str) -> Result<(), Box<dyn Error>> {
+fn grid_csv<T: std::fmt::Display>(grid: &Array2<T>) -> String {
+    grid.rows()
+        .into_iter()
+        .map(|row| row.iter().map(ToString::to_string).collect::<Vec<_>>().join(",") + "\n")
+        .collect()
+}
+
+fn create_video(frames_dir: &str, frame_pattern: &FramePattern, output_path: &str) -> Result<(), Box<dyn Error>> {
     let framerate = 10;
     let output = Command::new("ffmpeg")
         .arg("-y") // Overwrite output file if it exists
         .arg("-framerate")
         .arg(framerate.to_string())
         .arg("-i")
-        .arg(format!("{}/state_%04d.png", frames_dir))
+        .arg(format!("{}/{}", frames_dir, frame_pattern.ffmpeg_pattern()))
         .arg("-c:v")
         .arg("libx264")
         .arg("-pix_fmt")
@@ -93,245 +503,853 @@ fn create_video(frames_dir: &str, output_path: &str) -> Result<(), Box<dyn Error
         .output()?;
 
     if !output.status.success() {
-        eprintln!(
-            "ffmpeg error: {}",
+        return Err(ElFarolError::Render(format!(
+            "ffmpeg exited with {}: {}",
+            output.status,
             String::from_utf8_lossy(&output.stderr)
-        );
+        ))
+        .into());
     }
 
     Ok(())
 }
 
+/// How the per-frame grid states are rendered.
+#[derive(Debug, Clone)]
+struct GridOptions {
+    /// Mark cells whose policy changed since the previous rendered frame
+    show_changes: bool,
+    /// Outline every cell in gray
+    grid_lines: bool,
+    /// Render every Nth frame with NxN blocks pooled into one cell; 1 renders everything
+    downsample: usize,
+    /// Also render error maps with this colormap
+    error_colormap: Option<Colormap>,
+    /// Also render prediction maps
+    prediction_map: bool,
+    /// Only render the last frame, to `final_state.png` in the plots directory
+    final_only: bool,
+    /// File names of the rendered frames
+    frame_pattern: FramePattern,
+    /// Colors of the strategies
+    palette: StrategyPalette,
+}
+
+impl Default for GridOptions {
+    fn default() -> Self {
+        Self {
+            show_changes: false,
+            grid_lines: false,
+            downsample: 1,
+            error_colormap: None,
+            prediction_map: false,
+            final_only: false,
+            frame_pattern: FramePattern::default(),
+            palette: StrategyPalette::default(),
+        }
+    }
+}
+
+/// Shrinks a frame by pooling each `factor`×`factor` block of cells (smaller at the right and
+/// bottom edges) into one: the majority policy and the mean prediction.
+fn downsample_frame(frame: &Frame, factor: usize) -> Frame {
+    let pooled_dim = |len: usize| len.div_ceil(factor);
+    let (rows, cols) = frame.policy_ids.dim();
+    let block = |r: usize, c: usize| {
+        s![r * factor..((r + 1) * factor).min(rows), c * factor..((c + 1) * factor).min(cols)]
+    };
+    Frame {
+        policy_ids: majority_pool(&frame.policy_ids, factor),
+        predictions: Array2::from_shape_fn((pooled_dim(rows), pooled_dim(cols)), |(r, c)| {
+            frame.predictions.slice(block(r, c)).mean().unwrap_or(0.0)
+        }),
+        ..frame.clone()
+    }
+}
+
+/// Pools each `factor`×`factor` block of policy ids into its most common id; ties go to the lower id.
+fn majority_pool(policy_ids: &Array2<StrategyId>, factor: usize) -> Array2<StrategyId> {
+    let (rows, cols) = policy_ids.dim();
+    Array2::from_shape_fn((rows.div_ceil(factor), cols.div_ceil(factor)), |(r, c)| {
+        let block = policy_ids.slice(s![
+            r * factor..((r + 1) * factor).min(rows),
+            c * factor..((c + 1) * factor).min(cols)
+        ]);
+        let mut counts = [0usize; StrategyId::MAX as usize + 1];
+        for &id in block.iter() {
+            counts[id as usize] += 1;
+        }
+        counts
+            .iter()
+            .enumerate()
+            .max_by_key(|&(id, &count)| (count, std::cmp::Reverse(id)))
+            .map_or(0, |(id, _)| id as StrategyId)
+    })
+}
+
 fn visualize_simulation(
     simulation_data: &SimulationData,
     grid_states_dir: &str,
     plots_dir: &str,
+    show_progress: bool,
+    options: GridOptions,
+    scale: PlotScale,
+    manifest: &mut Manifest,
 ) -> Result<(), Box<dyn Error>> {
-    plot_statistics(simulation_data, plots_dir)?;
-    plot_strategy_predictions(simulation_data, plots_dir)?;
-    let pb = ProgressBar::new(simulation_data.frames.len() as u64);
+    ensure_frames(simulation_data)?;
+    plot_statistics(simulation_data, plots_dir, scale, options.palette, manifest)?;
+    let path = plot_strategy_predictions(simulation_data, plots_dir, scale, options.palette)?;
+    manifest.record(&path, ArtifactKind::Plot);
+    let strategies = simulation_data.config.strategy_names();
+    if options.final_only {
+        let index = simulation_data.frames.len() - 1;
+        let last = &simulation_data.frames[index];
+        let frame = match options.downsample {
+            1 => Cow::Borrowed(last),
+            n => Cow::Owned(downsample_frame(last, n)),
+        };
+        let path = Path::new(plots_dir).join("final_state.png");
+        visualize_grid_state(&frame, None, index, &strategies, options.palette, &path, options.grid_lines)?;
+        manifest.record(&path, ArtifactKind::GridState);
+        return Ok(());
+    }
+
+    // Frames are numbered consecutively after skipping so ffmpeg finds all of them
+    let frames: Vec<Cow<Frame>> = simulation_data
+        .frames
+        .iter()
+        .step_by(options.downsample)
+        .map(|frame| match options.downsample {
+            1 => Cow::Borrowed(frame),
+            n => Cow::Owned(downsample_frame(frame, n)),
+        })
+        .collect();
+    let pb = if show_progress {
+        ProgressBar::new(frames.len() as u64)
+    } else {
+        ProgressBar::hidden()
+    };
     pb.set_style(
         ProgressStyle::default_bar()
             .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")?
             .progress_chars("#>-"),
     );
 
-    for (i, frame) in simulation_data.frames.iter().enumerate() {
-        visualize_grid_state(
-            frame,
-            i,
-            &simulation_data.config.initial_strategies,
-            grid_states_dir,
-        )?;
+    let error_maps_dir = Path::new(plots_dir).join("error_maps");
+    if options.error_colormap.is_some() {
+        fs::create_dir_all(&error_maps_dir)?;
+    }
+    let prediction_maps_dir = Path::new(plots_dir).join("prediction_maps");
+    if options.prediction_map {
+        fs::create_dir_all(&prediction_maps_dir)?;
+    }
+
+    for (i, frame) in frames.iter().enumerate() {
+        if let Some(colormap) = options.error_colormap {
+            let errors = frame.predictions.mapv(|prediction| (prediction - frame.attendance_ratio).abs());
+            let path = error_maps_dir.join(format!("error_{:04}.png", i));
+            render_value_map(&errors, colormap, &path)?;
+            manifest.record(&path, ArtifactKind::ErrorMap);
+        }
+        if options.prediction_map {
+            let path = prediction_maps_dir.join(format!("prediction_{:04}.png", i));
+            render_value_map(&frame.predictions, Colormap::BlueRed, &path)?;
+            manifest.record(&path, ArtifactKind::PredictionMap);
+        }
+        let previous = if options.show_changes && i > 0 {
+            frames.get(i - 1).map(|previous| previous.as_ref())
+        } else {
+            None
+        };
+        let path = Path::new(grid_states_dir).join(options.frame_pattern.file_name(i));
+        visualize_grid_state(frame, previous, i, &strategies, options.palette, &path, options.grid_lines)?;
+        manifest.record(&path, ArtifactKind::GridState);
         pb.inc(1);
     }
     pb.finish_with_message("visualization complete");
     Ok(())
 }
 
+/// Rejects data without frames (e.g. a truncated file) before any plot indexes into it.
+fn ensure_frames(simulation_data: &SimulationData) -> Result<(), Box<dyn Error>> {
+    if simulation_data.frames.is_empty() {
+        return Err("no frames to visualize".into());
+    }
+    Ok(())
+}
+
 fn plot_statistics(
     simulation_data: &SimulationData,
     output_dir: &str,
+    scale: PlotScale,
+    palette: StrategyPalette,
+    manifest: &mut Manifest,
 ) -> Result<(), Box<dyn Error>> {
+    let statistics = compute_statistics(simulation_data);
+    let strategies = simulation_data.config.strategy_names();
+    for path in [
+        plot_attendance(&statistics, simulation_data.config.threshold, output_dir, scale)?,
+        plot_threshold_deviation(&statistics, output_dir, scale)?,
+        plot_strategy_distribution(&statistics, output_dir, &strategies, scale, palette)?,
+    ] {
+        manifest.record(&path, ArtifactKind::Plot);
+    }
+    Ok(())
+}
+
+fn compute_statistics(simulation_data: &SimulationData) -> HashMap<String, Vec<f64>> {
     let mut statistics: HashMap<String, Vec<f64>> = HashMap::new();
-    let total_agents = (simulation_data.frames[0].policy_ids.nrows()
-        * simulation_data.frames[0].policy_ids.ncols()) as f64;
+    let strategies = simulation_data.config.strategy_names();
+    let total_agents = simulation_data
+        .frames
+        .first()
+        .map_or(0, |frame| frame.policy_ids.len())
+        .max(1) as f64;
 
     for frame in &simulation_data.frames {
         statistics
             .entry("attendance_ratio".to_string())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(frame.attendance_ratio);
+        statistics
+            .entry("threshold_deviation".to_string())
+            .or_default()
+            .push((frame.attendance_ratio - frame.threshold).abs());
 
         let mut strategy_counts: HashMap<String, usize> = HashMap::new();
         for policy_id in frame.policy_ids.iter() {
-            let policy_name = &simulation_data.config.initial_strategies[*policy_id as usize];
-            *strategy_counts.entry(policy_name.clone()).or_insert(0) += 1;
+            *strategy_counts.entry(strategy_name(&strategies, *policy_id as usize)).or_insert(0) += 1;
         }
 
         for (strategy, count) in strategy_counts {
             let ratio = count as f64 / total_agents;
             statistics
                 .entry(format!("strategy_{}", strategy))
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(ratio);
         }
     }
-    plot_attendance(&statistics, output_dir)?;
-    plot_strategy_distribution(&statistics, output_dir, &simulation_data.config.initial_strategies)?;
+    statistics
+}
+
+/// Writes one row per frame with the attendance ratio and the share of every strategy as an
+/// Arrow IPC file, which pandas and polars can load directly.
+#[cfg(feature = "arrow")]
+fn write_statistics_arrow(
+    simulation_data: &SimulationData,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    use arrow::array::{ArrayRef, Float64Array, UInt64Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::ipc::writer::FileWriter;
+    use arrow::record_batch::RecordBatch;
+    use std::sync::Arc;
+
+    let strategies = &simulation_data.config.strategy_names();
+    let mut fields = vec![
+        Field::new("iteration", DataType::UInt64, false),
+        Field::new("attendance_ratio", DataType::Float64, false),
+    ];
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from_iter_values(0..simulation_data.frames.len() as u64)),
+        Arc::new(Float64Array::from_iter_values(
+            simulation_data.frames.iter().map(|frame| frame.attendance_ratio),
+        )),
+    ];
+
+    for (strategy_idx, strategy_name) in strategies.iter().enumerate() {
+        fields.push(Field::new(format!("strategy_{}", strategy_name), DataType::Float64, false));
+        columns.push(Arc::new(Float64Array::from_iter_values(
+            simulation_data.frames.iter().map(|frame| {
+                let count = frame
+                    .policy_ids
+                    .iter()
+                    .filter(|&&id| id as usize == strategy_idx)
+                    .count();
+                count as f64 / frame.policy_ids.len().max(1) as f64
+            }),
+        )));
+    }
+
+    let schema = Arc::new(Schema::new(fields));
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+    let mut writer = FileWriter::try_new(fs::File::create(path)?, &schema)?;
+    writer.write(&batch)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Renders attendance, strategy distribution, strategy predictions and the final grid state
+/// into a single `dashboard.png`.
+fn plot_dashboard(
+    simulation_data: &SimulationData,
+    output_dir: &str,
+    scale: PlotScale,
+    palette: StrategyPalette,
+) -> Result<PathBuf, Box<dyn Error>> {
+    ensure_frames(simulation_data)?;
+    let statistics = compute_statistics(simulation_data);
+    let strategies = &simulation_data.config.strategy_names();
+
+    let path = Path::new(output_dir).join("dashboard.png");
+    let root = BitMapBackend::new(&path, scale.canvas((2120, 1400))).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let panels = root.split_evenly((2, 2));
+    draw_attendance(&panels[0], &statistics, simulation_data.config.threshold, scale)?;
+    draw_strategy_distribution(&panels[1], &statistics, strategies, scale, palette)?;
+    draw_strategy_predictions(&panels[2], simulation_data, scale, palette)?;
+    if let Some(frame) = simulation_data.frames.last() {
+        draw_grid_state(&panels[3], frame, strategies, scale, palette)?;
+    }
+
+    root.present()?;
+    Ok(path.clone())
+}
+
+/// Draws the grid of `frame` as colored cells into `area`, scaled to fit.
+fn draw_grid_state(
+    area: &DrawingArea<BitMapBackend, Shift>,
+    frame: &Frame,
+    strategies: &[String],
+    scale: PlotScale,
+    palette: StrategyPalette,
+) -> Result<(), Box<dyn Error>> {
+    let (area_width, area_height) = area.dim_in_pixel();
+    let (grid_height, grid_width) = (frame.policy_ids.nrows(), frame.policy_ids.ncols());
+    if grid_height == 0 || grid_width == 0 {
+        return Ok(());
+    }
+    let legend_width = scale.px(300);
+    let cell_size = ((area_width.saturating_sub(legend_width)) as usize / grid_width)
+        .min(area_height as usize / grid_height)
+        .max(1) as i32;
+
+    for ((r, c), policy_id) in frame.policy_ids.indexed_iter() {
+        let (x, y) = (c as i32 * cell_size, r as i32 * cell_size);
+        area.draw(&Rectangle::new(
+            [(x, y), (x + cell_size, y + cell_size)],
+            palette.plot_color(*policy_id as usize).filled(),
+        ))?;
+    }
+
+    let legend_x = grid_width as i32 * cell_size + scale.offset(20);
+    let legend_style = TextStyle::from(("sans-serif", scale.font(16.0)).into_font()).color(&BLACK);
+    let swatch = scale.offset(12);
+    for (i, strategy_name) in strategies.iter().enumerate() {
+        let y_pos = scale.offset(20 + i as i32 * 25);
+        area.draw(&Rectangle::new(
+            [(legend_x, y_pos), (legend_x + swatch, y_pos + swatch)],
+            palette.plot_color(i).filled(),
+        ))?;
+        area.draw(&Text::new(
+            strategy_name.as_str(),
+            (legend_x + scale.offset(18), y_pos),
+            legend_style.clone(),
+        ))?;
+    }
+
     Ok(())
 }
 
 fn plot_strategy_predictions(
     simulation_data: &SimulationData,
     output_dir: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
+    scale: PlotScale,
+    palette: StrategyPalette,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
     let path = Path::new(output_dir).join("strategy_predictions.png");
-    let root = BitMapBackend::new(&path, (1200, 800)).into_drawing_area();
+    let root = BitMapBackend::new(&path, scale.canvas((1200, 800))).into_drawing_area();
     root.fill(&WHITE)?;
+    draw_strategy_predictions(&root, simulation_data, scale, palette)?;
+    root.present()?;
+    Ok(path.clone())
+}
 
-    let initial_strategies = &simulation_data.config.initial_strategies;
-    let mut strategy_prediction_series: HashMap<String, Vec<(usize, f64)>> = HashMap::new();
+fn draw_strategy_predictions(
+    root: &DrawingArea<BitMapBackend, Shift>,
+    simulation_data: &SimulationData,
+    scale: PlotScale,
+    palette: StrategyPalette,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let initial_strategies = &simulation_data.config.strategy_names();
+    let mut strategy_prediction_series: HashMap<String, Vec<(usize, PredictionBand)>> =
+        HashMap::new();
 
     for (frame_idx, frame) in simulation_data.frames.iter().enumerate() {
-        let mut predictions_this_frame: HashMap<String, f64> = HashMap::new();
-        // Use indexed_iter to get both the position and the policy_id
-        for (pos, policy_id) in frame.policy_ids.indexed_iter() {
-            let strategy_name = &initial_strategies[*policy_id as usize];
-            // If we haven't recorded a prediction for this strategy in this frame yet
-            if !predictions_this_frame.contains_key(strategy_name) {
-                // Get the prediction from the corresponding position in the predictions array
-                let prediction = frame.predictions[pos];
-                predictions_this_frame.insert(strategy_name.clone(), prediction);
-            }
-        }
-
-        // Add the found predictions for this frame to our time series data
-        for (strategy_name, prediction) in predictions_this_frame {
-            strategy_prediction_series.entry(strategy_name).or_default().push((frame_idx, prediction));
+        // Add the prediction bands for this frame to our time series data
+        for (policy_id, band) in prediction_bands(frame) {
+            strategy_prediction_series
+                .entry(strategy_name(initial_strategies, policy_id))
+                .or_default()
+                .push((frame_idx, band));
         }
     }
 
     let max_iterations = simulation_data.frames.len();
-    let mut chart = ChartBuilder::on(&root)
-        .caption("Strategy predictions over time", ("sans-serif", 40))
-        .margin_left(20)
-        .margin_right(40)
-        .margin_top(20)
-        .margin_bottom(20)
-        .x_label_area_size(60)
-        .y_label_area_size(60)
+    let mut chart = ChartBuilder::on(root)
+        .caption("Strategy predictions over time", ("sans-serif", scale.font(40.0)))
+        .margin_left(scale.px(20))
+        .margin_right(scale.px(40))
+        .margin_top(scale.px(20))
+        .margin_bottom(scale.px(20))
+        .x_label_area_size(scale.px(60))
+        .y_label_area_size(scale.px(60))
         .build_cartesian_2d(0f32..max_iterations as f32, 0f32..1f32)?;
 
     chart
         .configure_mesh()
         .x_desc("Iteration")
         .y_desc("Predicted attendance ratio")
-        .axis_desc_style(("sans-serif", 32).into_font())
-        .label_style(("sans-serif", 24).into_font())
+        .axis_desc_style(("sans-serif", scale.font(32.0)).into_font())
+        .label_style(("sans-serif", scale.font(24.0)).into_font())
         .x_label_formatter(&|x| format!("{}", *x as i32))
         .draw()?;
 
+    let legend_line = scale.offset(20);
     for (i, strategy_name) in initial_strategies.iter().enumerate() {
         if let Some(preds) = strategy_prediction_series.get(strategy_name) {
-            let color = get_strategy_plot_color(i);
+            let color = palette.plot_color(i);
+            // Shaded min–max band: upper edge left to right, then lower edge back.
+            let band_outline: Vec<(f32, f32)> = preds
+                .iter()
+                .map(|(x, band)| (*x as f32, band.max as f32))
+                .chain(preds.iter().rev().map(|(x, band)| (*x as f32, band.min as f32)))
+                .collect();
+            chart.draw_series(std::iter::once(Polygon::new(band_outline, color.mix(0.2))))?;
             chart
                 .draw_series(LineSeries::new(
-                    preds.iter().map(|(x, y)| (*x as f32, *y as f32)),
+                    preds.iter().map(|(x, band)| (*x as f32, band.mean as f32)),
                     &color,
                 ))?
                 .label(strategy_name)
-                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &color));
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + legend_line, y)], color));
         }
     }
 
     chart
         .configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
-        .label_font(("sans-serif", 16))
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .label_font(("sans-serif", scale.font(16.0)))
         .draw()?;
 
     Ok(())
 }
 
+/// Returns one prediction per policy id present in `frame`, taken from the first cell (in row-major
+/// order) that uses the policy. Agents with the same deterministic policy all predict the same value.
+fn representative_predictions(frame: &Frame) -> HashMap<usize, f64> {
+    let mut predictions: HashMap<usize, f64> = HashMap::new();
+    // Use indexed_iter to get both the position and the policy_id
+    for (pos, policy_id) in frame.policy_ids.indexed_iter() {
+        predictions
+            .entry(*policy_id as usize)
+            .or_insert_with(|| frame.predictions[pos]);
+    }
+    predictions
+}
+
+/// Mean, minimum and maximum prediction over all cells that use one policy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PredictionBand {
+    mean: f64,
+    min: f64,
+    max: f64,
+}
+
+/// Returns the prediction band of every policy id present in `frame`, computed over all of its cells.
+/// Deterministic policies yield a band of zero width; stochastic ones spread out.
+fn prediction_bands(frame: &Frame) -> HashMap<usize, PredictionBand> {
+    let mut sums: HashMap<usize, (f64, usize, f64, f64)> = HashMap::new();
+    for (pos, policy_id) in frame.policy_ids.indexed_iter() {
+        let prediction = frame.predictions[pos];
+        let entry = sums
+            .entry(*policy_id as usize)
+            .or_insert((0.0, 0, f64::INFINITY, f64::NEG_INFINITY));
+        entry.0 += prediction;
+        entry.1 += 1;
+        entry.2 = entry.2.min(prediction);
+        entry.3 = entry.3.max(prediction);
+    }
+    sums.into_iter()
+        .map(|(policy_id, (sum, count, min, max))| {
+            (policy_id, PredictionBand { mean: sum / count as f64, min, max })
+        })
+        .collect()
+}
+
 fn plot_attendance(
     statistics: &HashMap<String, Vec<f64>>,
+    threshold: f64,
     output_dir: &str,
+    scale: PlotScale,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = Path::new(output_dir).join("attendance.png");
+    let root = BitMapBackend::new(&path, scale.canvas((800, 600))).into_drawing_area();
+    root.fill(&WHITE)?;
+    draw_attendance(&root, statistics, threshold, scale)?;
+    root.present()?;
+    Ok(path.clone())
+}
+
+/// Draws the attendance ratio with a dashed reference line at the comfort `threshold`, so
+/// over- and under-crowded iterations stand out.
+fn draw_attendance(
+    root: &DrawingArea<BitMapBackend, Shift>,
+    statistics: &HashMap<String, Vec<f64>>,
+    threshold: f64,
+    scale: PlotScale,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let attendance = statistics
         .get("attendance_ratio")
         .ok_or("No attendance data found")?;
+    draw_time_series(
+        root,
+        attendance,
+        "Attendance ratio over time",
+        "Attendance ratio",
+        &RED,
+        Some(threshold),
+        scale,
+    )
+}
 
-    let path = Path::new(output_dir).join("attendance.png");
-    let root = BitMapBackend::new(&path, (800, 600)).into_drawing_area();
+fn plot_threshold_deviation(
+    statistics: &HashMap<String, Vec<f64>>,
+    output_dir: &str,
+    scale: PlotScale,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let deviation = statistics
+        .get("threshold_deviation")
+        .ok_or("No threshold deviation data found")?;
+    let path = Path::new(output_dir).join("threshold_deviation.png");
+    let root = BitMapBackend::new(&path, scale.canvas((800, 600))).into_drawing_area();
     root.fill(&WHITE)?;
+    draw_time_series(
+        &root,
+        deviation,
+        "Distance from threshold (lower is better)",
+        "|Attendance - threshold|",
+        &BLUE,
+        None,
+        scale,
+    )?;
+    root.present()?;
+    Ok(path.clone())
+}
 
-    let mut chart = ChartBuilder::on(&root)
-        .caption("Attendance ratio over time", ("sans-serif", 40))
-        .margin_left(20)
-        .margin_right(40)
-        .margin_top(20)
-        .margin_bottom(20)
-        .x_label_area_size(60)
-        .y_label_area_size(60)
-        .build_cartesian_2d(0f32..attendance.len() as f32, 0f32..1f32)?;
+/// Draws `values` (one per iteration, in [0, 1]) as a single line, plus a dashed gray horizontal
+/// line at `reference` if given.
+fn draw_time_series(
+    root: &DrawingArea<BitMapBackend, Shift>,
+    values: &[f64],
+    caption: &str,
+    y_desc: &str,
+    color: &RGBColor,
+    reference: Option<f64>,
+    scale: PlotScale,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut chart = ChartBuilder::on(root)
+        .caption(caption, ("sans-serif", scale.font(40.0)))
+        .margin_left(scale.px(20))
+        .margin_right(scale.px(40))
+        .margin_top(scale.px(20))
+        .margin_bottom(scale.px(20))
+        .x_label_area_size(scale.px(60))
+        .y_label_area_size(scale.px(60))
+        .build_cartesian_2d(0f32..values.len() as f32, 0f32..1f32)?;
 
     chart
         .configure_mesh()
         .x_desc("Iteration")
-        .y_desc("Attendance ratio")
-        .axis_desc_style(("sans-serif", 32).into_font())
-        .label_style(("sans-serif", 24).into_font())
+        .y_desc(y_desc)
+        .axis_desc_style(("sans-serif", scale.font(32.0)).into_font())
+        .label_style(("sans-serif", scale.font(24.0)).into_font())
         .x_label_formatter(&|x| format!("{}", *x as i32))
         .draw()?;
 
     chart.draw_series(LineSeries::new(
-        attendance
+        values
             .iter()
             .enumerate()
             .map(|(x, &y)| (x as f32, y as f32)),
-        &RED,
+        color,
     ))?;
 
+    if let Some(reference) = reference {
+        chart.draw_series(DashedLineSeries::new(
+            [(0.0, reference as f32), (values.len() as f32, reference as f32)],
+            scale.px(10),
+            scale.px(6),
+            RGBColor(100, 100, 100).stroke_width(scale.px(2)),
+        ))?;
+    }
+
     Ok(())
 }
 
-fn get_strategy_colors() -> Vec<Rgb<u8>> {
-    vec![
-        Rgb([220, 50, 47]),   // Red
-        Rgb([38, 139, 210]),  // Blue  
-        Rgb([133, 153, 0]),   // Green
-        Rgb([181, 137, 0]),   // Yellow
-        Rgb([211, 54, 130]),  // Magenta
-        Rgb([42, 161, 152]),  // Cyan
-        Rgb([88, 110, 117]),  // Gray
-        Rgb([108, 113, 196]), // Purple
-        Rgb([147, 161, 161]), // Light Gray
-        Rgb([255, 85, 85]),   // Light Red
-        Rgb([85, 170, 255]),  // Light Blue
-        Rgb([170, 255, 85]),  // Light Green
-        Rgb([255, 170, 85]),  // Orange
-        Rgb([255, 85, 170]),  // Pink
-        Rgb([85, 255, 170]),  // Mint
-        Rgb([170, 85, 255]),  // Violet
-        Rgb([85, 85, 85]),    // Dark Gray
-    ]
-}
-
-fn get_strategy_plot_color(index: usize) -> RGBColor {
-    let colors = [
-        (220, 50, 47),   // Red
-        (38, 139, 210),  // Blue  
-        (133, 153, 0),   // Green
-        (181, 137, 0),   // Yellow
-        (211, 54, 130),  // Magenta
-        (42, 161, 152),  // Cyan
-        (88, 110, 117),  // Gray
-        (108, 113, 196), // Purple
-        (147, 161, 161), // Light Gray
-        (255, 85, 85),   // Light Red
-        (85, 170, 255),  // Light Blue
-        (170, 255, 85),  // Light Green
-        (255, 170, 85),  // Orange
-        (255, 85, 170),  // Pink
-        (85, 255, 170),  // Mint
-        (170, 85, 255),  // Violet
-        (85, 85, 85),    // Dark Gray
-    ];
-    let (r, g, b) = colors[index % colors.len()];
-    RGBColor(r, g, b)
+/// Temperatures covered by `--temperature-sweep`, log-spaced between the two ends.
+const SWEEP_TEMPERATURE_RANGE: (f64, f64) = (0.01, 100.0);
+const SWEEP_POINTS: usize = 9;
+/// Upper bound on the iterations of every sweep run, the sweep is meant as a quick diagnostic.
+const SWEEP_ITERATIONS: usize = 50;
+
+/// Config for the runs of a temperature sweep: the recorded run's config, shortened to at most
+/// [`SWEEP_ITERATIONS`] iterations.
+fn sweep_config(config: &SerializableSimulationConfig) -> Result<SimulationConfig, ElFarolError> {
+    Ok(SimulationConfig {
+        num_iterations: config.num_iterations.min(SWEEP_ITERATIONS),
+        // Per-round frames and histograms are not needed for the metric
+        record_every_round: false,
+        record_prediction_histogram: false,
+        record_batch_attendance: false,
+        ..config.to_simulation_config()?
+    })
+}
+
+/// Plots attendance std against temperature on a log axis and marks the most (lowest std) and
+/// least (highest std) stable temperature.
+fn plot_temperature_sweep(
+    points: &[(f64, f64)],
+    output_dir: &str,
+    scale: PlotScale,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let by_std = |a: &&(f64, f64), b: &&(f64, f64)| a.1.total_cmp(&b.1);
+    let (most_stable, least_stable) = match (points.iter().min_by(by_std), points.iter().max_by(by_std)) {
+        (Some(&min), Some(&max)) => (min, max),
+        _ => return Err("No temperature sweep results to plot".into()),
+    };
+    let min_temperature = points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let max_temperature = points.iter().map(|p| p.0).fold(0.0, f64::max);
+
+    let path = Path::new(output_dir).join("temperature_sweep.png");
+    let root = BitMapBackend::new(&path, scale.canvas((800, 600))).into_drawing_area();
+    root.fill(&WHITE)?;
+    let mut chart = ChartBuilder::on(&root)
+        .caption("Attendance std vs temperature", ("sans-serif", scale.font(40.0)))
+        .margin_left(scale.px(20))
+        .margin_right(scale.px(40))
+        .margin_top(scale.px(20))
+        .margin_bottom(scale.px(20))
+        .x_label_area_size(scale.px(60))
+        .y_label_area_size(scale.px(60))
+        .build_cartesian_2d(
+            (min_temperature * 0.8..max_temperature * 1.25).log_scale(),
+            0f64..(least_stable.1 * 1.1).max(0.01),
+        )?;
+
+    chart
+        .configure_mesh()
+        .x_desc("Temperature")
+        .y_desc("Attendance std")
+        .axis_desc_style(("sans-serif", scale.font(32.0)).into_font())
+        .label_style(("sans-serif", scale.font(24.0)).into_font())
+        .draw()?;
+
+    chart.draw_series(LineSeries::new(points.iter().copied(), &BLUE))?;
+    chart.draw_series(points.iter().map(|&point| Circle::new(point, scale.px(4), BLUE.filled())))?;
+    chart
+        .draw_series(std::iter::once(Circle::new(most_stable, scale.px(8), GREEN.filled())))?
+        .label(format!("Most stable (T = {:.3})", most_stable.0))
+        .legend(move |(x, y)| Circle::new((x, y), scale.px(5), GREEN.filled()));
+    chart
+        .draw_series(std::iter::once(Circle::new(least_stable, scale.px(8), RED.filled())))?
+        .label(format!("Least stable (T = {:.3})", least_stable.0))
+        .legend(move |(x, y)| Circle::new((x, y), scale.px(5), RED.filled()));
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .label_font(("sans-serif", scale.font(20.0)))
+        .draw()?;
+
+    root.present()?;
+    Ok(path.clone())
+}
+
+const BASE_STRATEGY_COLORS: [(u8, u8, u8); 17] = [
+    (220, 50, 47),   // Red
+    (38, 139, 210),  // Blue
+    (133, 153, 0),   // Green
+    (181, 137, 0),   // Yellow
+    (211, 54, 130),  // Magenta
+    (42, 161, 152),  // Cyan
+    (88, 110, 117),  // Gray
+    (108, 113, 196), // Purple
+    (147, 161, 161), // Light Gray
+    (255, 85, 85),   // Light Red
+    (85, 170, 255),  // Light Blue
+    (170, 255, 85),  // Light Green
+    (255, 170, 85),  // Orange
+    (255, 85, 170),  // Pink
+    (85, 255, 170),  // Mint
+    (170, 85, 255),  // Violet
+    (85, 85, 85),    // Dark Gray
+];
+
+/// Color of strategy `id`, used by the grid renders and all plots so a strategy looks the same
+/// everywhere. The first `base_colors` ids (at most all 17) take the fixed base palette; later ids
+/// step around the HSV hue wheel by the golden ratio, so every strategy gets its own color
+/// without knowing how many there are.
+fn color_for_strategy(id: usize, base_colors: usize) -> Rgb<u8> {
+    let base_colors = base_colors.min(BASE_STRATEGY_COLORS.len());
+    let (r, g, b) = match BASE_STRATEGY_COLORS[..base_colors].get(id) {
+        Some(&color) => color,
+        None => {
+            let hue = ((id - base_colors) as f64 * GOLDEN_RATIO_CONJUGATE).fract() * 360.0;
+            hsv_to_rgb(hue, 0.75, 0.85)
+        }
+    };
+    Rgb([r, g, b])
+}
+
+/// 1 / φ, the hue step between generated strategy colors.
+const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+
+fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> (u8, u8, u8) {
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let m = value - chroma;
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let to_u8 = |c: f64| ((c + m) * 255.0).round() as u8;
+    (to_u8(r), to_u8(g), to_u8(b))
+}
+
+/// Colormaps for scalar maps with values in [0, 1].
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Colormap {
+    /// Perceptually uniform, dark purple (#440154) to yellow (#fde725)
+    Viridis,
+    /// Perceptually uniform, black (#000004) to light yellow (#fcfdbf)
+    Magma,
+    /// Black to white
+    Grayscale,
+    /// Pure blue to pure red
+    BlueRed,
+}
+
+impl Colormap {
+    fn color(self, t: f64) -> Rgb<u8> {
+        match self {
+            Colormap::Viridis => viridis(t),
+            Colormap::Magma => magma(t),
+            Colormap::Grayscale => grayscale(t),
+            Colormap::BlueRed => blue_red(t),
+        }
+    }
+}
+
+/// Samples of viridis at t = 0, 1/8, ..., 1.
+const VIRIDIS_STOPS: [[u8; 3]; 9] = [
+    [0x44, 0x01, 0x54],
+    [0x47, 0x2d, 0x7b],
+    [0x3b, 0x52, 0x8b],
+    [0x2c, 0x72, 0x8e],
+    [0x21, 0x91, 0x8c],
+    [0x28, 0xae, 0x80],
+    [0x5e, 0xc9, 0x62],
+    [0xad, 0xdc, 0x30],
+    [0xfd, 0xe7, 0x25],
+];
+
+/// Samples of magma at t = 0, 1/8, ..., 1.
+const MAGMA_STOPS: [[u8; 3]; 9] = [
+    [0x00, 0x00, 0x04],
+    [0x1c, 0x10, 0x44],
+    [0x4f, 0x12, 0x7b],
+    [0x81, 0x25, 0x81],
+    [0xb5, 0x36, 0x7a],
+    [0xe5, 0x50, 0x64],
+    [0xfb, 0x87, 0x61],
+    [0xfe, 0xc2, 0x87],
+    [0xfc, 0xfd, 0xbf],
+];
+
+/// Linearly interpolates between equally spaced color stops; `t` is clamped to [0, 1].
+fn interpolate_stops(stops: &[[u8; 3]], t: f64) -> Rgb<u8> {
+    let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let lower = (position.floor() as usize).min(stops.len() - 2);
+    let fraction = position - lower as f64;
+    let channel = |k: usize| {
+        let (a, b) = (stops[lower][k] as f64, stops[lower + 1][k] as f64);
+        (a + (b - a) * fraction).round() as u8
+    };
+    Rgb([channel(0), channel(1), channel(2)])
+}
+
+fn viridis(t: f64) -> Rgb<u8> {
+    interpolate_stops(&VIRIDIS_STOPS, t)
+}
+
+fn magma(t: f64) -> Rgb<u8> {
+    interpolate_stops(&MAGMA_STOPS, t)
+}
+
+fn grayscale(t: f64) -> Rgb<u8> {
+    let value = (t.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgb([value, value, value])
+}
+
+fn blue_red(t: f64) -> Rgb<u8> {
+    interpolate_stops(&[[0, 0, 255], [255, 0, 0]], t)
+}
+
+/// Renders a grid of values in [0, 1] with the same cell size as the grid states, followed by a
+/// vertical color bar running from 0 (bottom) to 1 (top).
+fn render_value_map(values: &Array2<f64>, colormap: Colormap, path: &Path) -> Result<(), Box<dyn Error>> {
+    let cell_size = 8u32;
+    let (bar_gap, bar_width) = (10u32, 16u32);
+    let (rows, cols) = values.dim();
+    let grid_height = (rows as u32 * cell_size).max(1);
+    let img_width = cols as u32 * cell_size + bar_gap + bar_width;
+    let mut img = RgbImage::from_pixel(img_width, grid_height, Rgb([255u8, 255, 255]));
+
+    for ((r, c), &value) in values.indexed_iter() {
+        draw_filled_rect_mut(
+            &mut img,
+            Rect::at((c as u32 * cell_size) as i32, (r as u32 * cell_size) as i32).of_size(cell_size, cell_size),
+            colormap.color(value),
+        );
+    }
+    let bar_x = (cols as u32 * cell_size + bar_gap) as i32;
+    for y in 0..grid_height {
+        let t = 1.0 - y as f64 / (grid_height - 1).max(1) as f64;
+        draw_filled_rect_mut(&mut img, Rect::at(bar_x, y as i32).of_size(bar_width, 1), colormap.color(t));
+    }
+    img.save(path)?;
+    Ok(())
 }
 
 fn plot_strategy_distribution(
     statistics: &HashMap<String, Vec<f64>>,
     output_dir: &str,
     initial_strategies: &[String],
-) -> Result<(), Box<dyn std::error::Error>> {
+    scale: PlotScale,
+    palette: StrategyPalette,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
     let path = Path::new(output_dir).join("strategy_distribution.png");
-    let root = BitMapBackend::new(&path, (1060, 600)).into_drawing_area();
+    let root = BitMapBackend::new(&path, scale.canvas((1060, 600))).into_drawing_area();
     root.fill(&WHITE)?;
+    draw_strategy_distribution(&root, statistics, initial_strategies, scale, palette)?;
+    root.present()?;
+    Ok(path.clone())
+}
 
+fn draw_strategy_distribution(
+    root: &DrawingArea<BitMapBackend, Shift>,
+    statistics: &HashMap<String, Vec<f64>>,
+    initial_strategies: &[String],
+    scale: PlotScale,
+    palette: StrategyPalette,
+) -> Result<(), Box<dyn std::error::Error>> {
     let max_iterations = statistics
         .iter()
         .filter(|(k, _)| k.starts_with("strategy_"))
@@ -345,22 +1363,22 @@ fn plot_strategy_distribution(
         max_iterations as f32
     };
 
-    let mut chart = ChartBuilder::on(&root)
-        .caption("Strategy distribution over time", ("sans-serif", 40))
-        .margin_left(20)
-        .margin_right(300)
-        .margin_top(20)
-        .margin_bottom(20)
-        .x_label_area_size(60)
-        .y_label_area_size(80)
+    let mut chart = ChartBuilder::on(root)
+        .caption("Strategy distribution over time", ("sans-serif", scale.font(40.0)))
+        .margin_left(scale.px(20))
+        .margin_right(scale.px(300))
+        .margin_top(scale.px(20))
+        .margin_bottom(scale.px(20))
+        .x_label_area_size(scale.px(60))
+        .y_label_area_size(scale.px(80))
         .build_cartesian_2d(0f32..x_axis_max, 0f32..1f32)?;
 
     chart
         .configure_mesh()
         .x_desc("Iteration")
         .y_desc("Strategy ratio")
-        .axis_desc_style(("sans-serif", 32).into_font())
-        .label_style(("sans-serif", 24).into_font())
+        .axis_desc_style(("sans-serif", scale.font(32.0)).into_font())
+        .label_style(("sans-serif", scale.font(24.0)).into_font())
         .x_label_formatter(&|x| format!("{}", *x as i32))
         .draw()?;
 
@@ -374,7 +1392,7 @@ fn plot_strategy_distribution(
             if values.is_empty() {
                 continue;
             }
-            let color = get_strategy_plot_color(strategy_idx);
+            let color = palette.plot_color(strategy_idx);
             legend_items.push((strategy_name.clone(), color));
 
             chart.draw_series(LineSeries::new(
@@ -388,42 +1406,61 @@ fn plot_strategy_distribution(
     }
 
     // Draw manual legend in the right margin area
-    let legend_start_x = 780; // Start legend in the right margin
-    let legend_style = TextStyle::from(("sans-serif", 16).into_font()).color(&BLACK);
+    let legend_start_x = root.dim_in_pixel().0 as i32 - scale.offset(280); // Start legend in the right margin
+    let legend_style = TextStyle::from(("sans-serif", scale.font(16.0)).into_font()).color(&BLACK);
     
     for (i, (strategy_name, color)) in legend_items.iter().enumerate() {
-        let y_pos = 80 + i as i32 * 25;
+        let y_pos = scale.offset(80 + i as i32 * 25);
         
         // Draw color line
         root.draw(&PathElement::new(
-            vec![(legend_start_x, y_pos), (legend_start_x + 20, y_pos)],
-            color.stroke_width(2),
+            vec![(legend_start_x, y_pos), (legend_start_x + scale.offset(20), y_pos)],
+            color.stroke_width(scale.px(2)),
         ))?;
         
         // Draw strategy name
         root.draw(&Text::new(
             strategy_name.as_str(),
-            (legend_start_x + 25, y_pos - 5),
+            (legend_start_x + scale.offset(25), y_pos - scale.offset(5)),
             legend_style.clone(),
         ))?;
     }
 
-    root.present()?;
     Ok(())
 }
 
+/// Cells whose policy differs between `previous` and `current`; empty if the grid shapes differ.
+fn changed_cells(previous: &Frame, current: &Frame) -> Vec<(usize, usize)> {
+    if previous.policy_ids.dim() != current.policy_ids.dim() {
+        return Vec::new();
+    }
+    current
+        .policy_ids
+        .indexed_iter()
+        .filter(|(pos, id)| previous.policy_ids[*pos] != **id)
+        .map(|(pos, _)| pos)
+        .collect()
+}
+
+/// Renders `frame` to the image file `path`. With a `previous` frame, cells that changed policy
+/// since then are marked with a black dot. `grid_lines` outlines every cell in gray.
 fn visualize_grid_state(
     frame: &Frame,
+    previous: Option<&Frame>,
     iteration_num: usize,
     strategies: &[String],
-    grid_states_dir: &str,
+    palette: StrategyPalette,
+    path: &Path,
+    grid_lines: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let (grid_height, grid_width) = (
         frame.policy_ids.nrows(),
         frame.policy_ids.ncols(),
     );
+    // Guards the percentages below against a 0×0 grid
+    let total_agents = (grid_width * grid_height).max(1) as f64;
     let cell_size = 8u32; // Increased from 20 to make cells more visible
-    let legend_width = 300u32;
+    let legend_width = 380u32;
     let info_height = 120u32; // More space for text information
     let img_width = grid_width as u32 * cell_size + legend_width;
     let img_height = grid_height as u32 * cell_size + info_height;
@@ -431,7 +1468,7 @@ fn visualize_grid_state(
     let mut img = RgbImage::new(img_width, img_height);
     draw_filled_rect_mut(&mut img, Rect::at(0, 0).of_size(img_width, img_height), Rgb([255u8, 255, 255]));
 
-    let strategy_colors = get_strategy_colors();
+    let predictions = representative_predictions(frame);
 
     // Count strategy distribution
     let mut strategy_counts: std::collections::HashMap<usize, usize> = std::collections::HashMap::new();
@@ -443,7 +1480,7 @@ fn visualize_grid_state(
     for r in 0..grid_height {
         for c in 0..grid_width {
             let policy_id = frame.policy_ids[[r, c]] as usize;
-            let color = strategy_colors[policy_id % strategy_colors.len()];
+            let color = palette.color(policy_id);
             let x = c as i32 * cell_size as i32;
             let y = r as i32 * cell_size as i32;
             
@@ -453,6 +1490,27 @@ fn visualize_grid_state(
                 Rect::at(x, y).of_size(cell_size, cell_size),
                 color,
             );
+            if grid_lines {
+                draw_hollow_rect_mut(
+                    &mut img,
+                    Rect::at(x, y).of_size(cell_size, cell_size),
+                    Rgb([160u8, 160, 160]),
+                );
+            }
+        }
+    }
+
+    // Mark cells that switched policy since the previous frame
+    if let Some(previous) = previous {
+        let dot_size = (cell_size / 2).max(1);
+        let offset = ((cell_size - dot_size) / 2) as i32;
+        for (r, c) in changed_cells(previous, frame) {
+            draw_filled_rect_mut(
+                &mut img,
+                Rect::at(c as i32 * cell_size as i32 + offset, r as i32 * cell_size as i32 + offset)
+                    .of_size(dot_size, dot_size),
+                Rgb([0u8, 0, 0]),
+            );
         }
     }
 
@@ -461,7 +1519,7 @@ fn visualize_grid_state(
     let legend_start_y = 20i32;
     for (i, _strategy_name) in strategies.iter().enumerate() {
         let y_pos = legend_start_y + 25 + (i as i32 * 18);
-        let color = strategy_colors[i % strategy_colors.len()];
+        let color = palette.color(i);
         
         // Draw color swatch
         draw_filled_rect_mut(
@@ -490,11 +1548,15 @@ fn visualize_grid_state(
             
             // Count for this strategy
             let count = strategy_counts.get(&i).unwrap_or(&0);
-            let percentage = (*count as f64 / (grid_width * grid_height) as f64) * 100.0;
+            let percentage = (*count as f64 / total_agents) * 100.0;
             
-            // Draw strategy name and percentage
+            // Draw strategy name, percentage and current prediction
+            let label = match predictions.get(&i) {
+                Some(prediction) => format!("{} ({:.1}%) pred={:.0}%", strategy_name, percentage, prediction * 100.0),
+                None => format!("{} ({:.1}%)", strategy_name, percentage),
+            };
             root.draw(&Text::new(
-                format!("{} ({:.1}%)", strategy_name, percentage),
+                label,
                 (legend_x + 18, y_pos),
                 legend_entry_style.clone(),
             ))?;
@@ -514,7 +1576,7 @@ fn visualize_grid_state(
         root.draw(&Text::new(
             format!("Attendance: {:.1}% ({} agents)", 
                 frame.attendance_ratio * 100.0,
-                (frame.attendance_ratio * (grid_width * grid_height) as f64) as usize
+                (frame.attendance_ratio * total_agents) as usize
             ),
             (20, info_y_start + 25),
             info_style.clone(),
@@ -522,10 +1584,10 @@ fn visualize_grid_state(
 
         // Find and display dominant strategy
         if let Some((dominant_id, dominant_count)) = strategy_counts.iter().max_by_key(|(_, &count)| count) {
-            let dominant_percentage = (*dominant_count as f64 / (grid_width * grid_height) as f64) * 100.0;
+            let dominant_percentage = (*dominant_count as f64 / total_agents) * 100.0;
             root.draw(&Text::new(
                 format!("Dominant: {} ({:.1}%)", 
-                    strategies[*dominant_id], 
+                    strategy_name(strategies, *dominant_id),
                     dominant_percentage
                 ),
                 (20, info_y_start + 50),
@@ -534,8 +1596,442 @@ fn visualize_grid_state(
         }
     }
 
-    let path = format!("{}/state_{:04}.png", grid_states_dir, iteration_num);
-    img.save(&path)?;
+    img.save(path)?;
 
     Ok(())
-} 
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use el_farol_lib::simulation_logic::policy::PolicySpec;
+    use el_farol_lib::simulation_logic::simulation::{read_policy_grid, InitPattern, NeighborDistance};
+    use el_farol_lib::DATA_FORMAT_VERSION;
+    use ndarray::Array2;
+
+    fn test_data(num_frames: usize) -> SimulationData {
+        let config = SerializableSimulationConfig {
+            format_version: DATA_FORMAT_VERSION,
+            name: "test".to_string(),
+            description: "test description".to_string(),
+            grid_size: 4,
+            neighbor_distance: NeighborDistance::new(1),
+            temperature: 1.0,
+            policy_retention_rate: 0.5,
+            num_iterations: num_frames,
+            rounds_per_update: 1,
+            initial_strategies: vec![PolicySpec::AlwaysGo, PolicySpec::NeverGo],
+            init_pattern: InitPattern::Random,
+            threshold: 0.6,
+            seed: Some(0),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            ..SerializableSimulationConfig::from(&SimulationConfig::default())
+        };
+        let frames = (0..num_frames)
+            .map(|i| Frame {
+                policy_ids: Array2::from_shape_fn((4, 4), |(r, c)| ((r + c + i) % 2) as u8),
+                predictions: Array2::from_shape_fn((4, 4), |(r, c)| ((r + c + i) % 2) as f64),
+                attendance_ratio: 0.5,
+                round: 0,
+                prediction_histogram: None,
+                batch_attendance: None,
+                threshold: 0.6,
+            })
+            .collect();
+        SimulationData { config, frames }
+    }
+
+    fn temp_output_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("el_farol_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_dashboard_is_written() {
+        let dir = temp_output_dir("dashboard");
+        plot_dashboard(&test_data(3), &dir.to_string_lossy(), PlotScale(1.0), StrategyPalette::default()).unwrap();
+        assert!(dir.join("dashboard.png").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_statistics_arrow_row_count() {
+        use arrow::ipc::reader::FileReader;
+
+        let dir = temp_output_dir("arrow");
+        let path = dir.join("statistics.arrow");
+        write_statistics_arrow(&test_data(5), &path).unwrap();
+
+        let reader = FileReader::try_new(fs::File::open(&path).unwrap(), None).unwrap();
+        let rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+        assert_eq!(rows, 5);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_strategy_colors_are_distinct() {
+        // Every possible strategy id, for any number of base colors
+        let num_ids = StrategyId::MAX as usize + 1;
+        for base_colors in [0, 5, 17, 40] {
+            let palette = StrategyPalette(base_colors);
+            let colors: std::collections::HashSet<_> = (0..num_ids).map(|id| palette.color(id)).collect();
+            assert_eq!(colors.len(), num_ids, "{} base colors", base_colors);
+            for id in 0..num_ids {
+                let RGBColor(r, g, b) = palette.plot_color(id);
+                assert!(colors.contains(&Rgb([r, g, b])));
+            }
+        }
+        // Colors do not depend on how many strategies there are
+        assert_eq!(StrategyPalette(5).color(3), StrategyPalette::default().color(3));
+        assert_ne!(StrategyPalette(5).color(5), StrategyPalette::default().color(5));
+    }
+
+    #[test]
+    fn test_grid_and_plot_colors_match() {
+        let dir = temp_output_dir("colors");
+        let mut frame = test_data(1).frames.remove(0);
+        // Ids beyond the strategy list, e.g. from a policy the config does not name, also as the
+        // dominant strategy
+        for r in 0..3 {
+            for c in 0..3 {
+                frame.policy_ids[[r, c]] = 3;
+            }
+        }
+        let strategies = ["Always Go".to_string(), "Never Go".to_string()];
+        let path = dir.join("state.png");
+        let palette = StrategyPalette::default();
+        visualize_grid_state(&frame, None, 0, &strategies, palette, &path, false).unwrap();
+
+        let img = image::open(&path).unwrap().to_rgb8();
+        for (r, c) in [(0, 0), (3, 3), (3, 2)] {
+            let id = frame.policy_ids[[r, c]] as usize;
+            let RGBColor(red, green, blue) = palette.plot_color(id);
+            assert_eq!(*img.get_pixel(c as u32 * 8 + 4, r as u32 * 8 + 4), Rgb([red, green, blue]), "id {}", id);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_representative_predictions() {
+        let frame = Frame {
+            policy_ids: Array2::from_shape_vec((2, 2), vec![1, 0, 0, 1]).unwrap(),
+            predictions: Array2::from_shape_vec((2, 2), vec![0.8, 0.1, 0.2, 0.9]).unwrap(),
+            attendance_ratio: 0.5,
+            round: 0,
+            prediction_histogram: None,
+            batch_attendance: None,
+            threshold: 0.6,
+        };
+        let predictions = representative_predictions(&frame);
+        assert_eq!(predictions.len(), 2);
+        assert_eq!(predictions[&0], 0.1);
+        assert_eq!(predictions[&1], 0.8);
+    }
+
+    #[test]
+    fn test_prediction_bands() {
+        // Policy 0 is deterministic (all cells agree), policy 1 is spread out.
+        let frame = Frame {
+            policy_ids: Array2::from_shape_vec((2, 2), vec![0, 0, 1, 1]).unwrap(),
+            predictions: Array2::from_shape_vec((2, 2), vec![0.4, 0.4, 0.2, 0.6]).unwrap(),
+            attendance_ratio: 0.5,
+            round: 0,
+            prediction_histogram: None,
+            batch_attendance: None,
+            threshold: 0.6,
+        };
+        let bands = prediction_bands(&frame);
+        assert_eq!(bands.len(), 2);
+        assert_eq!(bands[&0].min, bands[&0].max);
+        assert_eq!(bands[&0].mean, 0.4);
+        assert_eq!(bands[&1].min, 0.2);
+        assert_eq!(bands[&1].max, 0.6);
+        assert!((bands[&1].mean - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_final_only_renders_one_image() {
+        let dir = temp_output_dir("final_only");
+        let grid_states_dir = dir.join("grid_states");
+        fs::create_dir_all(&grid_states_dir).unwrap();
+        let options = GridOptions {
+            final_only: true,
+            ..GridOptions::default()
+        };
+        visualize_simulation(
+            &test_data(5),
+            &grid_states_dir.to_string_lossy(),
+            &dir.to_string_lossy(),
+            false,
+            options,
+            PlotScale(1.0),
+            &mut Manifest::new(&dir),
+        )
+        .unwrap();
+        assert!(dir.join("final_state.png").exists());
+        assert!(dir.join("attendance.png").exists());
+        assert_eq!(fs::read_dir(&grid_states_dir).unwrap().count(), 0);
+
+        assert!(Args::try_parse_from(["visualizer", "run.bin.xz", "--final-only", "--video"]).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_empty_data_is_rejected() {
+        let dir = temp_output_dir("empty");
+        let dir_str = dir.to_string_lossy();
+        let data = test_data(0);
+        let mut manifest = Manifest::new(&dir);
+        let err = visualize_simulation(&data, &dir_str, &dir_str, false, GridOptions::default(), PlotScale(1.0), &mut manifest)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "no frames to visualize");
+        assert!(plot_dashboard(&data, &dir_str, PlotScale(1.0), StrategyPalette::default()).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_zero_agent_grid_statistics() {
+        let mut data = test_data(1);
+        data.frames[0].policy_ids = Array2::zeros((0, 0));
+        data.frames[0].predictions = Array2::zeros((0, 0));
+        let statistics = compute_statistics(&data);
+        assert_eq!(statistics["attendance_ratio"], vec![0.5]);
+        assert!(statistics.values().flatten().all(|v| v.is_finite()));
+    }
+
+    #[test]
+    fn test_args_parse_quiet() {
+        let args = Args::try_parse_from(["visualizer", "run.bin.xz", "--quiet"]).unwrap();
+        assert!(args.quiet);
+        assert_eq!(args.input_file, PathBuf::from("run.bin.xz"));
+        assert!(!Args::try_parse_from(["visualizer", "run.bin.xz"]).unwrap().quiet);
+    }
+
+    #[test]
+    fn test_changed_cells() {
+        let data = test_data(2);
+        let (previous, current) = (&data.frames[0], &data.frames[1]);
+        // test_data flips every cell between consecutive frames
+        assert_eq!(changed_cells(previous, current).len(), 16);
+
+        let mut current = previous.clone();
+        current.policy_ids[[1, 2]] = 1 - current.policy_ids[[1, 2]];
+        current.policy_ids[[3, 0]] = 1 - current.policy_ids[[3, 0]];
+        assert_eq!(changed_cells(previous, &current), vec![(1, 2), (3, 0)]);
+        assert!(changed_cells(previous, previous).is_empty());
+    }
+
+    #[test]
+    fn test_plot_scale_doubles_canvas() {
+        assert_eq!(PlotScale(2.0).canvas((800, 600)), (1600, 1200));
+        assert_eq!(PlotScale(1.0).canvas((1060, 600)), (1060, 600));
+
+        let dir = temp_output_dir("scale");
+        let statistics = compute_statistics(&test_data(3));
+        plot_attendance(&statistics, 0.6, &dir.to_string_lossy(), PlotScale(2.0)).unwrap();
+        assert_eq!(image::image_dimensions(dir.join("attendance.png")).unwrap(), (1600, 1200));
+        fs::remove_dir_all(dir).unwrap();
+
+        assert!(parse_scale("0").is_err());
+        assert_eq!(parse_scale("1.5"), Ok(1.5));
+    }
+
+    #[test]
+    fn test_grid_lines() {
+        let args = Args::try_parse_from(["visualizer", "run.bin.xz", "--grid-lines"]).unwrap();
+        assert!(args.grid_lines);
+        assert!(!Args::try_parse_from(["visualizer", "run.bin.xz"]).unwrap().grid_lines);
+
+        let data = test_data(1);
+        let strategies = &data.config.strategy_names();
+        let plain_dir = temp_output_dir("grid_plain");
+        let lines_dir = temp_output_dir("grid_lines");
+        visualize_grid_state(&data.frames[0], None, 0, strategies, StrategyPalette::default(), &plain_dir.join("state_0000.png"), false).unwrap();
+        visualize_grid_state(&data.frames[0], None, 0, strategies, StrategyPalette::default(), &lines_dir.join("state_0000.png"), true).unwrap();
+        let plain = image::open(plain_dir.join("state_0000.png")).unwrap().to_rgb8();
+        let lines = image::open(lines_dir.join("state_0000.png")).unwrap().to_rgb8();
+
+        // Cell corners turn gray while cell centers keep their strategy color
+        assert_ne!(*plain.get_pixel(0, 0), Rgb([160, 160, 160]));
+        assert_eq!(*lines.get_pixel(0, 0), Rgb([160, 160, 160]));
+        assert_eq!(lines.get_pixel(4, 4), plain.get_pixel(4, 4));
+        fs::remove_dir_all(plain_dir).unwrap();
+        fs::remove_dir_all(lines_dir).unwrap();
+    }
+
+    #[test]
+    fn test_attendance_attribution_csv() {
+        let dir = temp_output_dir("attribution");
+        let path = dir.join("attendance_attribution.csv");
+        write_attendance_attribution(&test_data(2), &path).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        assert_eq!(csv, "strategy,attendance_share\n\"Always Go\",1\n\"Never Go\",0\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_majority_pool() {
+        let policy_ids = Array2::from_shape_vec(
+            (4, 4),
+            vec![
+                2, 2, 1, 0, //
+                2, 3, 2, 2, //
+                0, 1, 2, 1, //
+                1, 0, 2, 3,
+            ],
+        )
+        .unwrap();
+        assert_eq!(majority_pool(&policy_ids, 4), Array2::from_elem((1, 1), 2));
+        // Each 2×2 quadrant has its own majority; the bottom-left one ties and goes to the lower id
+        assert_eq!(
+            majority_pool(&policy_ids, 2),
+            Array2::from_shape_vec((2, 2), vec![2, 2, 0, 2]).unwrap()
+        );
+
+        let args = Args::try_parse_from(["visualizer", "run.bin.xz", "--downsample", "4"]).unwrap();
+        assert_eq!(args.downsample, 4);
+        assert!(Args::try_parse_from(["visualizer", "run.bin.xz", "--downsample", "0"]).is_err());
+        assert_eq!(downsample_frame(&test_data(1).frames[0], 3).predictions.dim(), (2, 2));
+    }
+
+    #[test]
+    fn test_colormap_endpoints() {
+        assert_eq!(viridis(0.0), Rgb([0x44, 0x01, 0x54]));
+        assert_eq!(viridis(1.0), Rgb([0xfd, 0xe7, 0x25]));
+        assert_eq!(magma(0.0), Rgb([0x00, 0x00, 0x04]));
+        assert_eq!(magma(1.0), Rgb([0xfc, 0xfd, 0xbf]));
+        assert_eq!(grayscale(0.0), Rgb([0, 0, 0]));
+        assert_eq!(grayscale(1.0), Rgb([255, 255, 255]));
+        // Out-of-range values saturate at the endpoints
+        assert_eq!(Colormap::Viridis.color(1.5), viridis(1.0));
+        assert_eq!(Colormap::Magma.color(-0.5), magma(0.0));
+
+        let args = Args::try_parse_from(["visualizer", "run.bin.xz", "--error-map", "--error-colormap", "magma"]).unwrap();
+        assert!(args.error_map);
+        assert_eq!(args.error_colormap, Colormap::Magma);
+        assert_eq!(Args::try_parse_from(["visualizer", "run.bin.xz"]).unwrap().error_colormap, Colormap::Viridis);
+
+        let dir = temp_output_dir("error_map");
+        let path = dir.join("error.png");
+        render_value_map(&Array2::from_elem((2, 2), 1.0), Colormap::Magma, &path).unwrap();
+        assert_eq!(*image::open(&path).unwrap().to_rgb8().get_pixel(0, 0), magma(1.0));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_prediction_map_colors() {
+        assert_eq!(blue_red(0.0), Rgb([0, 0, 255]));
+        assert_eq!(blue_red(1.0), Rgb([255, 0, 0]));
+        assert_eq!(blue_red(0.5), Rgb([128, 0, 128]));
+        assert!(Args::try_parse_from(["visualizer", "run.bin.xz", "--prediction-map"]).unwrap().prediction_map);
+
+        let dir = temp_output_dir("prediction_map");
+        let path = dir.join("prediction.png");
+        let predictions = Array2::from_shape_vec((1, 2), vec![0.0, 1.0]).unwrap();
+        render_value_map(&predictions, Colormap::BlueRed, &path).unwrap();
+        let img = image::open(&path).unwrap().to_rgb8();
+        assert_eq!(*img.get_pixel(0, 0), blue_red(0.0));
+        assert_eq!(*img.get_pixel(8, 0), blue_red(1.0));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_temperature_sweep_plot_is_written() {
+        let dir = temp_output_dir("temperature_sweep");
+        let points = [(0.01, 0.2), (0.1, 0.05), (1.0, 0.3)];
+        plot_temperature_sweep(&points, &dir.to_string_lossy(), PlotScale(1.0)).unwrap();
+        assert!(dir.join("temperature_sweep.png").exists());
+        assert!(plot_temperature_sweep(&[], &dir.to_string_lossy(), PlotScale(1.0)).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_frame_pattern() {
+        let pattern = FramePattern::parse("frame_{:06}.png").unwrap();
+        assert_eq!(pattern.file_name(7), "frame_000007.png");
+        assert_eq!(pattern.file_name(1234567), "frame_1234567.png");
+        assert_eq!(pattern.ffmpeg_pattern(), "frame_%06d.png");
+        assert_eq!(FramePattern::default().file_name(12), "state_0012.png");
+        assert_eq!(FramePattern::parse("{}.png").unwrap().file_name(12), "12.png");
+
+        for invalid in ["frame.png", "a_{:04}_{:04}.png", "frame_{:4}.png", "frame_{x}.png", "sub/{}.png"] {
+            assert!(FramePattern::parse(invalid).is_err(), "{} should be rejected", invalid);
+        }
+        let args = Args::try_parse_from(["visualizer", "run.bin.xz", "--frame-pattern", "f_{:05}.png"]).unwrap();
+        assert_eq!(args.frame_pattern.file_name(3), "f_00003.png");
+    }
+
+    #[test]
+    fn test_attendance_plot_with_custom_threshold() {
+        let dir = temp_output_dir("threshold_line");
+        let mut data = test_data(3);
+        data.config.threshold = 0.35;
+        plot_statistics(&data, &dir.to_string_lossy(), PlotScale(1.0), StrategyPalette::default(), &mut Manifest::new(&dir)).unwrap();
+        plot_dashboard(&data, &dir.to_string_lossy(), PlotScale(1.0), StrategyPalette::default()).unwrap();
+        assert!(dir.join("attendance.png").exists());
+        assert!(dir.join("dashboard.png").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_lists_core_artifacts() {
+        let dir = temp_output_dir("manifest");
+        let grid_states_dir = dir.join("grid_states");
+        fs::create_dir_all(&grid_states_dir).unwrap();
+        let mut manifest = Manifest::new(&dir);
+        visualize_simulation(
+            &test_data(3),
+            &grid_states_dir.to_string_lossy(),
+            &dir.to_string_lossy(),
+            false,
+            GridOptions::default(),
+            PlotScale(1.0),
+            &mut manifest,
+        )
+        .unwrap();
+        manifest.write().unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("manifest.json")).unwrap()).unwrap();
+        let files: HashMap<String, String> = json["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| (entry["path"].as_str().unwrap().to_string(), entry["kind"].as_str().unwrap().to_string()))
+            .collect();
+        for plot in ["attendance.png", "threshold_deviation.png", "strategy_distribution.png", "strategy_predictions.png"] {
+            assert_eq!(files.get(plot).map(String::as_str), Some("plot"), "{} missing", plot);
+        }
+        for i in 0..3 {
+            let frame = format!("grid_states/state_{:04}.png", i);
+            assert_eq!(files.get(&frame).map(String::as_str), Some("grid_state"), "{} missing", frame);
+        }
+        assert!(files.keys().all(|path| dir.join(path).exists()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_dump_frame_writes_grid_csvs() {
+        let dir = temp_output_dir("dump_frame");
+        let data = test_data(2);
+        let mut manifest = Manifest::new(&dir);
+        dump_frame(&data, 0, &dir, &mut manifest).unwrap();
+
+        for name in ["frame_0_policy_ids.csv", "frame_0_predictions.csv"] {
+            let csv = fs::read_to_string(dir.join(name)).unwrap();
+            let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+            assert_eq!(rows.len(), 4, "{}", name);
+            assert!(rows.iter().all(|row| row.len() == 4), "{}", name);
+        }
+        let ids = read_policy_grid(&dir.join("frame_0_policy_ids.csv")).unwrap();
+        assert_eq!(ids, data.frames[0].policy_ids);
+        assert_eq!(manifest.files.len(), 2);
+
+        let err = dump_frame(&data, 2, &dir, &mut manifest).unwrap_err();
+        assert_eq!(err.to_string(), "invalid configuration: frame 2 out of range, the run has 2 frames");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,62 @@
+//! Error type of the library's fallible functions.
+
+use std::error::Error;
+use std::io;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ElFarolError {
+    /// Reading or writing a file failed, e.g. `NotFound` for a missing input file
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// Data could not be decoded (or encoded): saved runs, frame streams, policy grids
+    #[error("decode error: {0}")]
+    Decode(#[source] Box<dyn Error + Send + Sync>),
+    /// Options that do not fit together or a saved config that cannot be used
+    #[error("invalid configuration: {0}")]
+    Config(String),
+    /// Producing an image or video failed
+    #[error("rendering failed: {0}")]
+    Render(String),
+}
+
+/// Bincode only reads from byte slices here, so even its I/O errors mean truncated data.
+impl From<bincode::Error> for ElFarolError {
+    fn from(error: bincode::Error) -> Self {
+        ElFarolError::Decode(error)
+    }
+}
+
+impl From<image::ImageError> for ElFarolError {
+    fn from(error: image::ImageError) -> Self {
+        match error {
+            image::ImageError::IoError(error) => ElFarolError::Io(error),
+            error => ElFarolError::Decode(Box::new(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation_logic::simulation::read_policy_grid;
+    use crate::SimulationData;
+    use std::path::Path;
+
+    fn is_not_found(error: &ElFarolError) -> bool {
+        matches!(error, ElFarolError::Io(error) if error.kind() == io::ErrorKind::NotFound)
+    }
+
+    #[test]
+    fn test_missing_input_file_is_not_found() {
+        let missing = Path::new("/nonexistent/el_farol/run.bin.xz");
+        assert!(is_not_found(&SimulationData::load(missing).unwrap_err()));
+        assert!(is_not_found(&read_policy_grid(&missing.with_extension("png")).unwrap_err()));
+        assert!(is_not_found(&read_policy_grid(&missing.with_extension("csv")).unwrap_err()));
+    }
+
+    #[test]
+    fn test_truncated_data_is_a_decode_error() {
+        let error = ElFarolError::from(bincode::deserialize::<u64>(&[1, 2]).unwrap_err());
+        assert!(matches!(error, ElFarolError::Decode(_)), "{:?}", error);
+    }
+}
@@ -0,0 +1,160 @@
+//! Frames written one file at a time while a simulation runs, so a run can be watched live.
+//!
+//! A stream is a directory containing `config.bin` (the bincode-encoded
+//! [`SerializableSimulationConfig`]), the frames as `frame_000000.bin`, `frame_000001.bin`, ...
+//! and an empty `done` file once the run has finished. Every file is written under a temporary
+//! name and then renamed, so readers never see a partially written file.
+
+use crate::{ElFarolError, Frame, SerializableSimulationConfig};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+const CONFIG_FILE: &str = "config.bin";
+const DONE_FILE: &str = "done";
+
+fn frame_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("frame_{:06}.bin", index))
+}
+
+fn write_atomically<T: Serialize>(path: &Path, value: &T) -> Result<(), ElFarolError> {
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, bincode::serialize(value)?)?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// Reads a file written by [`write_atomically`], or `None` if it does not exist (yet).
+fn read_if_exists<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, ElFarolError> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Appends frames to a stream directory.
+pub struct FrameStreamWriter {
+    dir: PathBuf,
+    next_index: usize,
+}
+
+impl FrameStreamWriter {
+    /// Creates `dir` if needed and writes the config. Frames and the `done` marker of an earlier
+    /// stream in the same directory are removed first so readers do not mix two runs.
+    pub fn create(dir: &Path, config: &SerializableSimulationConfig) -> Result<Self, ElFarolError> {
+        fs::create_dir_all(dir)?;
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            if name.starts_with("frame_") || name == DONE_FILE {
+                fs::remove_file(&path)?;
+            }
+        }
+        write_atomically(&dir.join(CONFIG_FILE), config)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            next_index: 0,
+        })
+    }
+
+    pub fn write_frame(&mut self, frame: &Frame) -> Result<(), ElFarolError> {
+        write_atomically(&frame_path(&self.dir, self.next_index), frame)?;
+        self.next_index += 1;
+        Ok(())
+    }
+
+    /// Marks the stream as complete.
+    pub fn finish(self) -> Result<(), ElFarolError> {
+        fs::write(self.dir.join(DONE_FILE), [])?;
+        Ok(())
+    }
+}
+
+/// Reads the frames of a stream directory as they appear.
+pub struct FrameStreamReader {
+    dir: PathBuf,
+    next_index: usize,
+}
+
+impl FrameStreamReader {
+    pub fn open(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            next_index: 0,
+        }
+    }
+
+    /// The stream's config, or `None` if the writer has not created it yet.
+    pub fn config(&self) -> Result<Option<SerializableSimulationConfig>, ElFarolError> {
+        read_if_exists(&self.dir.join(CONFIG_FILE))
+    }
+
+    /// Returns all frames written since the last call, in order.
+    pub fn read_new_frames(&mut self) -> Result<Vec<Frame>, ElFarolError> {
+        let mut frames = Vec::new();
+        while let Some(frame) = read_if_exists(&frame_path(&self.dir, self.next_index))? {
+            frames.push(frame);
+            self.next_index += 1;
+        }
+        Ok(frames)
+    }
+
+    /// Number of frames read so far.
+    pub fn frames_read(&self) -> usize {
+        self.next_index
+    }
+
+    /// True once the writer has finished; frames written before that may still be unread.
+    pub fn is_finished(&self) -> bool {
+        self.dir.join(DONE_FILE).exists()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation_logic::policy::{AlwaysGo, NeverGo};
+    use crate::simulation_logic::simulation::{Simulation, SimulationConfig};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_append_and_read_frames() {
+        let dir = std::env::temp_dir().join(format!("el_farol_stream_{}", std::process::id()));
+        let config = SimulationConfig {
+            grid_size: 3,
+            rounds_per_update: 1,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            ..Default::default()
+        };
+        let mut simulation = Simulation::new(config.clone());
+        let frames: Vec<Frame> = (0..3).map(|_| simulation.run_iteration()).collect();
+
+        let mut reader = FrameStreamReader::open(&dir);
+        assert!(reader.config().unwrap().is_none());
+
+        let mut writer = FrameStreamWriter::create(&dir, &SerializableSimulationConfig::from(&config)).unwrap();
+        assert_eq!(reader.config().unwrap().unwrap().grid_size, 3);
+        writer.write_frame(&frames[0]).unwrap();
+        writer.write_frame(&frames[1]).unwrap();
+
+        let first = reader.read_new_frames().unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[1].policy_ids, frames[1].policy_ids);
+        assert!(reader.read_new_frames().unwrap().is_empty());
+        assert!(!reader.is_finished());
+
+        writer.write_frame(&frames[2]).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(reader.read_new_frames().unwrap().len(), 1);
+        assert_eq!(reader.frames_read(), 3);
+        assert!(reader.is_finished());
+
+        // A new stream in the same directory starts from scratch
+        FrameStreamWriter::create(&dir, &SerializableSimulationConfig::from(&config)).unwrap();
+        assert!(FrameStreamReader::open(&dir).read_new_frames().unwrap().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,23 +1,75 @@
+pub mod error;
+pub mod frame_stream;
 pub mod simulation_logic;
 
+pub use error::ElFarolError;
+
 use ndarray::Array2;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use simulation_logic::game::GameMode;
+use simulation_logic::metrics::strategy_entropy;
+use simulation_logic::policy::PolicySpec;
+use simulation_logic::simulation::{InitPattern, NeighborDistance, Simulation, SimulationConfig, StatKind, UpdateOrder};
+use liblzma::read::XzDecoder;
+use liblzma::write::XzEncoder;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
 
 pub type StrategyId = u8;
 
+/// Version of the bincode layout of [`SimulationData`]. Bincode stores no field names, so adding,
+/// removing or reordering fields of the saved types breaks old files; bump this whenever that is
+/// intended (the schema test in this file fails until you do).
+pub const DATA_FORMAT_VERSION: u32 = 12;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SerializableSimulationConfig {
+    /// [`DATA_FORMAT_VERSION`] of the code that wrote the file
+    pub format_version: u32,
     pub name: String,
     pub description: String,
     pub grid_size: usize,
-    pub neighbor_distance: usize,
+    pub neighbor_distance: NeighborDistance,
     pub temperature: f64,
     pub policy_retention_rate: f64,
     pub num_iterations: usize,
     pub rounds_per_update: usize,
-    pub initial_strategies: Vec<String>,
-    pub start_random: bool,
+    /// Policies by strategy id; policies first seen during the run are appended as
+    /// [`PolicySpec::Named`]
+    pub initial_strategies: Vec<PolicySpec>,
+    pub init_pattern: InitPattern,
+    /// Attendance ratio at which the bar is considered overcrowded (before any schedule changes)
+    pub threshold: f64,
+    // The remaining fields mirror `SimulationConfig`, so a saved run can be re-run exactly
+    pub record_every_round: bool,
+    pub record_prediction_histogram: bool,
+    pub record_batch_attendance: bool,
+    pub threshold_schedule: Vec<(usize, f64)>,
+    pub use_chebyshev: bool,
+    pub stop_on_monoculture: bool,
+    pub stop_when_frozen: Option<usize>,
+    pub record_initial_frame: bool,
+    pub soft_decision: bool,
+    pub softness: f64,
+    pub go_if_below: bool,
+    pub performance_discount: f64,
+    pub distance_sigma: Option<f64>,
+    pub max_history: Option<usize>,
+    pub uphill_only: bool,
+    pub perception_noise: f64,
+    pub game_mode: GameMode,
+    pub warmup_rounds: usize,
+    pub stats_to_collect: BTreeSet<StatKind>,
+    pub update_order: UpdateOrder,
+    pub burn_in: usize,
+    /// Seed of the simulation's random number generator
+    pub seed: Option<u64>,
+    /// Version of this crate that produced the run
+    pub version: String,
+    /// UTC time the run was started, in RFC 3339 format
+    pub created_at: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -25,10 +77,688 @@ pub struct Frame {
     pub policy_ids: Array2<StrategyId>,
     pub predictions: Array2<f64>,
     pub attendance_ratio: f64,
+    /// Index of the game round within its update batch
+    pub round: usize,
+    /// Prediction counts per bucket over [0, 1], if enabled in the config
+    pub prediction_histogram: Option<Vec<u32>>,
+    /// Attendance ratio of every round of the update batch, oldest first, if enabled in the
+    /// config; only set on the frame recorded after adaptation
+    pub batch_attendance: Option<Vec<f64>>,
+    /// Comfort threshold in effect for this frame
+    pub threshold: f64,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SimulationData {
     pub config: SerializableSimulationConfig,
     pub frames: Vec<Frame>,
-} 
\ No newline at end of file
+}
+
+/// Attendance within this distance of the threshold counts as coordinated in [`Summary`].
+pub const COORDINATION_TOLERANCE: f64 = 0.05;
+
+/// Quick quality signal for a finished run, see [`SimulationData::summary`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub mean_attendance: f64,
+    pub attendance_std: f64,
+    /// Fraction of frames whose attendance is within [`COORDINATION_TOLERANCE`] of their threshold
+    pub within_threshold: f64,
+    /// Shannon entropy (bits) of the strategy distribution in the last frame
+    pub final_entropy: f64,
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "attendance {:.3} ± {:.3}, {:.1}% of frames within ±{} of threshold, final entropy {:.3} bits",
+            self.mean_attendance,
+            self.attendance_std,
+            self.within_threshold * 100.0,
+            COORDINATION_TOLERANCE,
+            self.final_entropy
+        )
+    }
+}
+
+impl SimulationData {
+    /// Reads a run written by [`SimulationData::save`].
+    ///
+    /// Files written with another [`DATA_FORMAT_VERSION`] are rejected with
+    /// `ElFarolError::Config` before the rest is decoded, since their layout differs.
+    pub fn load(path: &Path) -> Result<Self, ElFarolError> {
+        let mut decoded = Vec::new();
+        XzDecoder::new(File::open(path)?).read_to_end(&mut decoded)?;
+        // `format_version` is the first field, so it decodes on its own in every version
+        let format_version: u32 = bincode::deserialize(&decoded)?;
+        if format_version != DATA_FORMAT_VERSION {
+            return Err(ElFarolError::Config(format!(
+                "unsupported data format: expected version {}, found {}",
+                DATA_FORMAT_VERSION, format_version
+            )));
+        }
+        Ok(bincode::deserialize(&decoded)?)
+    }
+
+    /// Writes the run as xz-compressed bincode, the `.bin.xz` format of the binaries.
+    pub fn save(&self, path: &Path) -> Result<(), ElFarolError> {
+        let encoded = bincode::serialize(self)?;
+        let mut encoder = XzEncoder::new_parallel(File::create(path)?, 6);
+        encoder.write_all(&encoded)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    /// Frames recorded after the first `burn_in` rounds of the config, which summary metrics such
+    /// as [`SimulationData::summary`] are computed over. The initial frame counts as burn-in.
+    pub fn settled_frames(&self) -> &[Frame] {
+        let config = &self.config;
+        if config.burn_in == 0 {
+            return &self.frames;
+        }
+        let rounds_per_update = config.rounds_per_update.max(1);
+        let frames_per_iteration = if config.record_every_round { rounds_per_update + 1 } else { 1 };
+        // Iterations played entirely within the burn-in, then the per-round frames of the
+        // iteration it ends in; that iteration's frame after adaptation is already settled
+        let mut skipped = config.burn_in / rounds_per_update * frames_per_iteration;
+        if config.record_every_round {
+            skipped += config.burn_in % rounds_per_update;
+        }
+        if config.record_initial_frame {
+            skipped += 1;
+        }
+        self.frames.get(skipped..).unwrap_or_default()
+    }
+
+    /// Summarizes attendance over the [settled frames](SimulationData::settled_frames) and
+    /// strategy diversity at the end of the run. All values are 0.0 if no frames are left.
+    pub fn summary(&self) -> Summary {
+        let frames = self.settled_frames();
+        let n = frames.len().max(1) as f64;
+        let mean_attendance = frames.iter().map(|frame| frame.attendance_ratio).sum::<f64>() / n;
+        let variance = frames
+            .iter()
+            .map(|frame| (frame.attendance_ratio - mean_attendance).powi(2))
+            .sum::<f64>()
+            / n;
+        let within = frames
+            .iter()
+            .filter(|frame| (frame.attendance_ratio - frame.threshold).abs() <= COORDINATION_TOLERANCE)
+            .count();
+        Summary {
+            mean_attendance,
+            attendance_std: variance.sqrt(),
+            within_threshold: within as f64 / n,
+            final_entropy: frames
+                .last()
+                .map_or(0.0, |frame| strategy_entropy(&frame.policy_ids)),
+        }
+    }
+
+    /// Time to coordination: index of the first frame from which on the attendance stays within
+    /// `band` of the frame's threshold until the end of the run, or `None` if the last frame is
+    /// already outside the band (or there are no frames).
+    pub fn time_to_coordination(&self, band: f64) -> Option<usize> {
+        let coordinated_tail = self
+            .frames
+            .iter()
+            .rev()
+            .take_while(|frame| (frame.attendance_ratio - frame.threshold).abs() <= band)
+            .count();
+        (coordinated_tail > 0).then(|| self.frames.len() - coordinated_tail)
+    }
+
+    /// Estimate of the stationary attendance distribution: the fraction of the
+    /// [settled frames](SimulationData::settled_frames) whose attendance ratio falls into each of
+    /// `bins` equal buckets over [0, 1] (1.0 counts toward the last bucket). All fractions are 0.0
+    /// if no frames are left.
+    ///
+    /// Panics if `bins` is 0.
+    pub fn attendance_distribution(&self, bins: usize) -> Vec<f64> {
+        assert!(bins > 0, "An attendance distribution needs at least one bin");
+        let mut counts = vec![0usize; bins];
+        let frames = self.settled_frames();
+        for frame in frames {
+            let bin = (frame.attendance_ratio.clamp(0.0, 1.0) * bins as f64) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+        let total = frames.len().max(1) as f64;
+        counts.into_iter().map(|count| count as f64 / total).collect()
+    }
+
+    /// Share of the total attendance over all frames contributed by each strategy, indexed by
+    /// strategy id. An agent counts as attending if its prediction is below the frame's threshold
+    /// (at or above it with `go_if_below` unset), i.e. by the hard decision rule. All shares are
+    /// 0.0 if nobody ever went.
+    pub fn attendance_attribution(&self) -> Vec<f64> {
+        let max_id = self
+            .frames
+            .iter()
+            .flat_map(|frame| frame.policy_ids.iter())
+            .map(|&id| id as usize + 1)
+            .max()
+            .unwrap_or(0);
+        let mut attendance = vec![0usize; max_id.max(self.config.initial_strategies.len())];
+        for frame in &self.frames {
+            for (&id, &prediction) in frame.policy_ids.iter().zip(frame.predictions.iter()) {
+                if (prediction < frame.threshold) == self.config.go_if_below {
+                    attendance[id as usize] += 1;
+                }
+            }
+        }
+        let total = attendance.iter().sum::<usize>().max(1) as f64;
+        attendance.into_iter().map(|count| count as f64 / total).collect()
+    }
+}
+
+impl SerializableSimulationConfig {
+    /// Policy names indexed by strategy id.
+    pub fn strategy_names(&self) -> Vec<String> {
+        self.initial_strategies.iter().map(PolicySpec::name).collect()
+    }
+
+    /// Rebuilds the config the run was started with, policies included (see
+    /// [`PolicySpec::build`], whose error is returned for unknown `Named` policies). Together with
+    /// the saved seed this reproduces the run, as long as no policy draws from its own unseeded
+    /// randomness and no policy was first seen during the run (those become initial strategies here).
+    pub fn to_simulation_config(&self) -> Result<SimulationConfig, ElFarolError> {
+        Ok(SimulationConfig {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            grid_size: self.grid_size,
+            neighbor_distance: self.neighbor_distance,
+            temperature: self.temperature,
+            policy_retention_rate: self.policy_retention_rate,
+            num_iterations: self.num_iterations,
+            rounds_per_update: self.rounds_per_update,
+            initial_strategies: self.initial_strategies.iter().map(PolicySpec::build).collect::<Result<_, _>>()?,
+            init_pattern: self.init_pattern.clone(),
+            threshold: self.threshold,
+            record_every_round: self.record_every_round,
+            record_prediction_histogram: self.record_prediction_histogram,
+            record_batch_attendance: self.record_batch_attendance,
+            threshold_schedule: self.threshold_schedule.clone(),
+            use_chebyshev: self.use_chebyshev,
+            stop_on_monoculture: self.stop_on_monoculture,
+            stop_when_frozen: self.stop_when_frozen,
+            record_initial_frame: self.record_initial_frame,
+            seed: self.seed,
+            soft_decision: self.soft_decision,
+            softness: self.softness,
+            go_if_below: self.go_if_below,
+            performance_discount: self.performance_discount,
+            distance_sigma: self.distance_sigma,
+            max_history: self.max_history,
+            uphill_only: self.uphill_only,
+            perception_noise: self.perception_noise,
+            game_mode: self.game_mode,
+            warmup_rounds: self.warmup_rounds,
+            stats_to_collect: self.stats_to_collect.clone(),
+            update_order: self.update_order,
+            burn_in: self.burn_in,
+        })
+    }
+}
+
+impl From<&SimulationConfig> for SerializableSimulationConfig {
+    fn from(config: &SimulationConfig) -> Self {
+        Self {
+            format_version: DATA_FORMAT_VERSION,
+            name: config.name.clone(),
+            description: config.description.clone(),
+            grid_size: config.grid_size,
+            neighbor_distance: config.neighbor_distance,
+            temperature: config.temperature,
+            policy_retention_rate: config.policy_retention_rate,
+            num_iterations: config.num_iterations,
+            rounds_per_update: config.rounds_per_update,
+            initial_strategies: config.initial_strategies.iter().map(|p| p.spec()).collect(),
+            init_pattern: config.init_pattern.clone(),
+            threshold: config.threshold,
+            record_every_round: config.record_every_round,
+            record_prediction_histogram: config.record_prediction_histogram,
+            record_batch_attendance: config.record_batch_attendance,
+            threshold_schedule: config.threshold_schedule.clone(),
+            use_chebyshev: config.use_chebyshev,
+            stop_on_monoculture: config.stop_on_monoculture,
+            stop_when_frozen: config.stop_when_frozen,
+            record_initial_frame: config.record_initial_frame,
+            soft_decision: config.soft_decision,
+            softness: config.softness,
+            go_if_below: config.go_if_below,
+            performance_discount: config.performance_discount,
+            distance_sigma: config.distance_sigma,
+            max_history: config.max_history,
+            uphill_only: config.uphill_only,
+            perception_noise: config.perception_noise,
+            game_mode: config.game_mode,
+            warmup_rounds: config.warmup_rounds,
+            stats_to_collect: config.stats_to_collect.clone(),
+            update_order: config.update_order,
+            burn_in: config.burn_in,
+            seed: config.seed,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Runs a simulation for `config.num_iterations` iterations and returns all frames in memory.
+///
+/// Panics if the config cannot be used, see [`Simulation::try_new`].
+pub fn run_simulation(config: SimulationConfig) -> SimulationData {
+    run_simulation_with_callback(config, |_, _| {}).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Same as [`run_simulation`], but calls `on_frame` with the simulation and its latest frame after
+/// every iteration (e.g. for progress reporting or live logging), and returns the error from
+/// [`Simulation::try_new`] instead of panicking.
+pub fn run_simulation_with_callback<F>(config: SimulationConfig, mut on_frame: F) -> Result<SimulationData, ElFarolError>
+where
+    F: FnMut(&Simulation, &Frame),
+{
+    let mut serializable_config = SerializableSimulationConfig::from(&config);
+    let num_iterations = config.num_iterations;
+    let record_initial_frame = config.record_initial_frame;
+    let mut simulation = Simulation::try_new(config)?;
+    serializable_config.seed = Some(simulation.seed());
+
+    let mut frames = Vec::with_capacity(num_iterations + 1);
+    if record_initial_frame {
+        frames.push(simulation.initial_frame());
+    }
+    for iteration in 0..num_iterations {
+        let frame = simulation.run_iteration();
+        frames.extend(simulation.take_round_frames());
+        on_frame(&simulation, &frame);
+        frames.push(frame);
+
+        if simulation.should_stop() {
+            log::info!("Early-stopping condition reached after iteration {}, stopping", iteration);
+            break;
+        }
+    }
+
+    // Policies first seen during the run were assigned ids past the initial strategies
+    let num_initial = serializable_config.initial_strategies.len();
+    serializable_config
+        .initial_strategies
+        .extend(simulation.strategy_names().into_iter().skip(num_initial).map(PolicySpec::Named));
+
+    Ok(SimulationData {
+        config: serializable_config,
+        frames,
+    })
+}
+
+/// `n` values from `start` to `end` (both included) with a constant ratio between neighbors.
+/// Both ends must be positive.
+pub fn log_spaced(start: f64, end: f64, n: usize) -> Vec<f64> {
+    assert!(start > 0.0 && end > 0.0, "log-spaced values need positive ends");
+    if n == 1 {
+        return vec![start];
+    }
+    let (log_start, log_end) = (start.ln(), end.ln());
+    (0..n)
+        .map(|i| (log_start + (log_end - log_start) * i as f64 / (n - 1) as f64).exp())
+        .collect()
+}
+
+/// Runs `base` once per temperature and returns `(temperature, attendance std)` pairs in the
+/// order of `temperatures`. The std is taken over the second half of each run's frames, so the
+/// transient after initialization does not dominate. Keep `base.num_iterations` short, every
+/// temperature is a full run.
+pub fn temperature_sweep(base: &SimulationConfig, temperatures: &[f64]) -> Vec<(f64, f64)> {
+    temperatures
+        .iter()
+        .map(|&temperature| {
+            let data = run_simulation(SimulationConfig {
+                temperature,
+                ..base.clone()
+            });
+            let final_half = &data.frames[data.frames.len() / 2..];
+            let n = final_half.len().max(1) as f64;
+            let mean = final_half.iter().map(|frame| frame.attendance_ratio).sum::<f64>() / n;
+            let variance = final_half
+                .iter()
+                .map(|frame| (frame.attendance_ratio - mean).powi(2))
+                .sum::<f64>()
+                / n;
+            (temperature, variance.sqrt())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simulation_logic::game::DEFAULT_THRESHOLD;
+    use simulation_logic::policy::{AlwaysGo, NeverGo};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_run_simulation() {
+        let config = SimulationConfig {
+            grid_size: 4,
+            num_iterations: 2,
+            rounds_per_update: 1,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            ..Default::default()
+        };
+        let data = run_simulation(config);
+        assert_eq!(data.frames.len(), 2);
+        assert_eq!(data.config.num_iterations, 2);
+        assert_eq!(data.config.initial_strategies, vec![PolicySpec::AlwaysGo, PolicySpec::NeverGo]);
+        assert_eq!(data.config.strategy_names(), vec!["Always Go", "Never Go"]);
+    }
+
+    #[test]
+    fn test_load_rejects_other_format_versions() {
+        let config = SimulationConfig {
+            grid_size: 3,
+            num_iterations: 1,
+            rounds_per_update: 1,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            ..Default::default()
+        };
+        let mut data = run_simulation(config);
+        let path = std::env::temp_dir().join(format!("el_farol_format_{}.bin.xz", std::process::id()));
+        data.save(&path).unwrap();
+        assert_eq!(SimulationData::load(&path).unwrap().frames.len(), 1);
+
+        data.config.format_version = DATA_FORMAT_VERSION - 1;
+        data.save(&path).unwrap();
+        let error = SimulationData::load(&path).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "invalid configuration: unsupported data format: expected version {}, found {}",
+                DATA_FORMAT_VERSION,
+                DATA_FORMAT_VERSION - 1
+            )
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_reproducibility_metadata_round_trips() {
+        let config = SimulationConfig {
+            grid_size: 3,
+            num_iterations: 1,
+            rounds_per_update: 1,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            ..Default::default()
+        };
+        let data = run_simulation(config);
+        assert!(data.config.seed.is_some());
+        assert_eq!(data.config.version, env!("CARGO_PKG_VERSION"));
+        assert!(chrono::DateTime::parse_from_rfc3339(&data.config.created_at).is_ok());
+
+        let decoded: SimulationData = bincode::deserialize(&bincode::serialize(&data).unwrap()).unwrap();
+        assert_eq!(decoded.config.seed, data.config.seed);
+        assert_eq!(decoded.config.version, data.config.version);
+        assert_eq!(decoded.config.created_at, data.config.created_at);
+
+        let from_toml: SerializableSimulationConfig =
+            toml::from_str(&toml::to_string_pretty(&data.config).unwrap()).unwrap();
+        assert_eq!(from_toml.seed, data.config.seed);
+        assert_eq!(from_toml.created_at, data.config.created_at);
+        assert_eq!(from_toml.initial_strategies, data.config.initial_strategies);
+        assert_eq!(from_toml.threshold, DEFAULT_THRESHOLD);
+    }
+
+    #[test]
+    fn test_summary() {
+        let frame = |attendance_ratio: f64, policy_ids: Vec<StrategyId>| Frame {
+            policy_ids: Array2::from_shape_vec((2, 2), policy_ids).unwrap(),
+            predictions: Array2::zeros((2, 2)),
+            attendance_ratio,
+            round: 0,
+            prediction_histogram: None,
+            batch_attendance: None,
+            threshold: 0.6,
+        };
+        // One round per frame, so the burn-in below skips as many frames as rounds
+        let config = SimulationConfig {
+            rounds_per_update: 1,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            ..Default::default()
+        };
+        let data = SimulationData {
+            config: SerializableSimulationConfig::from(&config),
+            frames: vec![
+                frame(0.4, vec![0, 0, 0, 0]),
+                frame(0.6, vec![0, 0, 1, 1]),
+                frame(0.62, vec![0, 0, 1, 1]),
+                frame(0.8, vec![0, 1, 0, 1]),
+            ],
+        };
+        let summary = data.summary();
+        assert!((summary.mean_attendance - 0.605).abs() < 1e-12);
+        let variance = (0.205f64.powi(2) + 0.005f64.powi(2) + 0.015f64.powi(2) + 0.195f64.powi(2)) / 4.0;
+        assert!((summary.attendance_std - variance.sqrt()).abs() < 1e-12);
+        assert_eq!(summary.within_threshold, 0.5);
+        assert!((summary.final_entropy - 1.0).abs() < 1e-12);
+
+        // The burn-in frames are skipped, the final entropy is still the last frame's
+        let mut data = data;
+        data.config.burn_in = 2;
+        let summary = data.summary();
+        assert!((summary.mean_attendance - 0.71).abs() < 1e-12);
+        assert!((summary.attendance_std - 0.09).abs() < 1e-12);
+        assert_eq!(summary.within_threshold, 0.5);
+        assert!((summary.final_entropy - 1.0).abs() < 1e-12);
+
+        data.config.burn_in = 4;
+        assert_eq!(data.summary().mean_attendance, 0.0);
+    }
+
+    #[test]
+    fn test_attendance_attribution() {
+        // Only the "Always Go" agents (prediction 0) attend; "Never Go" agents predict 1
+        let policy_ids = Array2::from_shape_vec((2, 2), vec![0, 1, 1, 1]).unwrap();
+        let frame = Frame {
+            predictions: policy_ids.mapv(|id| id as f64),
+            policy_ids,
+            attendance_ratio: 0.25,
+            round: 0,
+            prediction_histogram: None,
+            batch_attendance: None,
+            threshold: 0.6,
+        };
+        let config = SimulationConfig {
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            ..Default::default()
+        };
+        let data = SimulationData {
+            config: SerializableSimulationConfig::from(&config),
+            frames: vec![frame.clone(), frame],
+        };
+        assert_eq!(data.attendance_attribution(), vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_temperature_sweep() {
+        let temperatures = log_spaced(0.01, 1.0, 3);
+        assert_eq!(temperatures.len(), 3);
+        assert!((temperatures[1] - 0.1).abs() < 1e-12);
+        assert!((temperatures[2] - 1.0).abs() < 1e-12);
+
+        let config = SimulationConfig {
+            grid_size: 4,
+            num_iterations: 4,
+            rounds_per_update: 2,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            seed: Some(1),
+            ..Default::default()
+        };
+        let sweep = temperature_sweep(&config, &temperatures);
+        assert_eq!(sweep.len(), temperatures.len());
+        for ((temperature, std), expected) in sweep.iter().zip(&temperatures) {
+            assert_eq!(temperature, expected);
+            assert!(std.is_finite() && *std >= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_attendance_distribution() {
+        let config = SimulationConfig {
+            rounds_per_update: 1,
+            initial_strategies: vec![Arc::new(AlwaysGo)],
+            ..Default::default()
+        };
+        let mut data = SimulationData {
+            config: SerializableSimulationConfig::from(&config),
+            frames: [0.0, 1.0, 0.1, 0.3, 0.6, 0.7, 1.0]
+                .into_iter()
+                .map(|attendance_ratio| Frame {
+                    policy_ids: Array2::zeros((1, 1)),
+                    predictions: Array2::zeros((1, 1)),
+                    attendance_ratio,
+                    round: 0,
+                    prediction_histogram: None,
+                    batch_attendance: None,
+                    threshold: 0.6,
+                })
+                .collect(),
+        };
+        let all = data.attendance_distribution(4);
+        assert!((all.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(all, [2.0 / 7.0, 1.0 / 7.0, 2.0 / 7.0, 2.0 / 7.0]);
+
+        // The first three rounds, one frame each, are discarded as transient
+        data.config.burn_in = 3;
+        let settled = data.attendance_distribution(4);
+        assert!((settled.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(settled, [0.0, 0.25, 0.5, 0.25]);
+
+        data.config.burn_in = 10;
+        assert_eq!(data.attendance_distribution(2), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_burn_in_counts_rounds() {
+        let run = |record_every_round: bool, record_initial_frame: bool, burn_in: usize| {
+            let config = SimulationConfig {
+                grid_size: 2,
+                num_iterations: 4,
+                rounds_per_update: 3,
+                initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+                record_every_round,
+                record_initial_frame,
+                burn_in,
+                seed: Some(1),
+                ..Default::default()
+            };
+            let data = run_simulation(config);
+            data.frames.len() - data.settled_frames().len()
+        };
+        // 5 rounds cover the first iteration and two rounds of the second
+        assert_eq!(run(false, false, 5), 1);
+        assert_eq!(run(false, true, 5), 2);
+        assert_eq!(run(true, false, 5), 4 + 2);
+        assert_eq!(run(true, true, 5), 1 + 4 + 2);
+        // Whole batches skip exactly their frames
+        assert_eq!(run(false, false, 6), 2);
+        assert_eq!(run(true, false, 6), 8);
+        assert_eq!(run(true, true, 0), 0);
+    }
+
+    #[test]
+    fn test_time_to_coordination() {
+        let frame = |attendance_ratio: f64| Frame {
+            policy_ids: Array2::zeros((1, 1)),
+            predictions: Array2::zeros((1, 1)),
+            attendance_ratio,
+            round: 0,
+            prediction_histogram: None,
+            batch_attendance: None,
+            threshold: 0.6,
+        };
+        let config = SimulationConfig {
+            initial_strategies: vec![Arc::new(AlwaysGo)],
+            ..Default::default()
+        };
+        let mut data = SimulationData {
+            config: SerializableSimulationConfig::from(&config),
+            // Passes through the band at iteration 2 before settling from iteration 5 on
+            frames: [0.2, 0.9, 0.62, 0.3, 0.8, 0.58, 0.61, 0.6, 0.63]
+                .into_iter()
+                .map(frame)
+                .collect(),
+        };
+        assert_eq!(data.time_to_coordination(0.05), Some(5));
+        assert_eq!(data.time_to_coordination(0.5), Some(0));
+
+        data.frames.push(frame(0.9));
+        assert_eq!(data.time_to_coordination(0.05), None);
+        data.frames.clear();
+        assert_eq!(data.time_to_coordination(0.05), None);
+    }
+
+    /// FNV-1a, so the golden below does not depend on the standard library's hasher.
+    fn fnv1a(bytes: &[u8]) -> u64 {
+        bytes
+            .iter()
+            .fold(0xcbf29ce484222325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+    }
+
+    #[test]
+    fn test_bincode_layout_is_unchanged() {
+        let config = SerializableSimulationConfig {
+            format_version: DATA_FORMAT_VERSION,
+            name: "golden".to_string(),
+            description: "schema test".to_string(),
+            grid_size: 2,
+            neighbor_distance: NeighborDistance::new(1),
+            temperature: 1.5,
+            policy_retention_rate: 0.25,
+            num_iterations: 1,
+            rounds_per_update: 3,
+            initial_strategies: vec![PolicySpec::AlwaysGo, PolicySpec::Constant { value: 0.5 }],
+            init_pattern: InitPattern::HalfAndHalf { left: 0, right: 1 },
+            threshold: 0.6,
+            record_every_round: true,
+            record_prediction_histogram: true,
+            record_batch_attendance: true,
+            threshold_schedule: vec![(5, 0.4)],
+            use_chebyshev: false,
+            stop_on_monoculture: true,
+            stop_when_frozen: Some(3),
+            record_initial_frame: false,
+            soft_decision: true,
+            softness: 0.05,
+            go_if_below: false,
+            performance_discount: 0.9,
+            distance_sigma: None,
+            max_history: Some(10),
+            uphill_only: false,
+            perception_noise: 0.1,
+            game_mode: GameMode::MinorityGame,
+            warmup_rounds: 2,
+            stats_to_collect: [StatKind::UniqueStrategies, StatKind::GridStability].into(),
+            update_order: UpdateOrder::RandomSequential,
+            burn_in: 20,
+            seed: Some(7),
+            version: "0.0.0".to_string(),
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+        };
+        let data = SimulationData {
+            config,
+            frames: vec![Frame {
+                policy_ids: Array2::from_shape_vec((2, 2), vec![0, 1, 0, 1]).unwrap(),
+                predictions: Array2::from_shape_vec((2, 2), vec![0.0, 0.5, 0.0, 0.5]).unwrap(),
+                attendance_ratio: 0.5,
+                round: 2,
+                prediction_histogram: Some(vec![1, 2]),
+                batch_attendance: Some(vec![0.25, 0.5, 0.5]),
+                threshold: 0.6,
+            }],
+        };
+        let bytes = bincode::serialize(&data).unwrap();
+        // If this fails, the saved layout changed and existing .bin.xz files no longer load. If
+        // that is intended, bump DATA_FORMAT_VERSION and update the expected pair.
+        assert_eq!((DATA_FORMAT_VERSION, bytes.len(), fnv1a(&bytes)), (12, 470, 0xb687_4271_bc30_780a));
+    }
+}
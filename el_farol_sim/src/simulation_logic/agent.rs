@@ -1,133 +1,308 @@
-use super::policy::Policy;
-use crate::simulation_logic::game::Game;
+use super::game::GameMode;
+use super::policy::{instance_for_agent, NeighborContext, Policy};
 use rand::seq::SliceRandom;
 use rand::Rng;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use std::fmt::Debug;
 
 
 #[derive(Debug)]
 pub struct Agent {
     current_policy: Arc<dyn Policy>,
-    pub performance_history: Vec<f64>, // Stores points now, not error
+    performance_history: Vec<f64>, // Stores points now, not error
     pub last_prediction: Option<f64>, // Stores the last prediction made by the policy
+    pub perception_bias: f64, // Added to every observed attendance ratio (imperfect information)
+    performance_cache: PerformanceCache,
+}
+
+/// Performance values memoized between changes of the history. Every method that changes
+/// `performance_history` resets it, marking the agent dirty.
+#[derive(Debug, Clone, Default)]
+struct PerformanceCache {
+    sum: OnceLock<f64>,
+    /// `(discount, value)` of the first discount asked for since the last change
+    discounted: OnceLock<(f64, f64)>,
 }
 
 impl Agent {
+    /// Creates an agent predicting with its own instance of `initial_policy`, see
+    /// [`instance_for_agent`].
     pub fn new(initial_policy: Arc<dyn Policy>) -> Self {
         Self {
-            current_policy: initial_policy,
+            current_policy: instance_for_agent(&initial_policy),
             performance_history: Vec::new(),
             last_prediction: None,
+            perception_bias: 0.0,
+            performance_cache: PerformanceCache::default(),
         }
     }
 
-    pub fn update_performance(&mut self, went_to_bar: bool, actual_attendance_ratio: f64) {
-        let bar_is_overcrowded = actual_attendance_ratio >= 0.6;
-        let score = match (went_to_bar, bar_is_overcrowded) {
-            (true, false) => 1.0, // Went to a non-crowded bar
-            (false, true) => 1.0, // Stayed home from a crowded bar
-            _ => 0.0,             // Other cases
-        };
+    /// Scores the round with the El Farol payoff, see [`GameMode::ElFarol`].
+    pub fn update_performance(&mut self, went_to_bar: bool, actual_attendance_ratio: f64, threshold: f64) {
+        self.record_score(GameMode::ElFarol.score(went_to_bar, actual_attendance_ratio, threshold));
+    }
+
+    pub fn record_score(&mut self, score: f64) {
         self.performance_history.push(score);
+        self.performance_cache = PerformanceCache::default();
+    }
+
+    /// Scores of the rounds since the last adaptation, oldest first.
+    pub fn performance_history(&self) -> &[f64] {
+        &self.performance_history
+    }
+
+    pub fn set_performance_history(&mut self, history: Vec<f64>) {
+        self.performance_history = history;
+        self.performance_cache = PerformanceCache::default();
     }
 
+    /// Sum of the scores since the last adaptation, memoized until the history changes.
     pub fn performance(&self) -> f64 {
+        *self
+            .performance_cache
+            .sum
+            .get_or_init(|| self.performance_history.iter().sum())
+    }
+
+    /// Performance with round `k` (0 = most recent) weighted by `discount^k`.
+    ///
+    /// Computed as the discounted mean `sum(score_k * d^k) / sum(d^k)` scaled by the number of
+    /// rounds, so that a discount of 1.0 equals the plain [`Agent::performance`] sum.
+    ///
+    /// Every agent is scored once per neighborhood it belongs to during adaptation, so the value
+    /// is memoized until the history changes.
+    pub fn discounted_performance(&self, discount: f64) -> f64 {
+        let &(cached_discount, value) = self
+            .performance_cache
+            .discounted
+            .get_or_init(|| (discount, self.compute_discounted_performance(discount)));
+        if cached_discount == discount {
+            value
+        } else {
+            self.compute_discounted_performance(discount)
+        }
+    }
+
+    fn compute_discounted_performance(&self, discount: f64) -> f64 {
         if self.performance_history.is_empty() {
             return 0.0;
         }
-        self.performance_history.iter().sum()
+        let mut weighted_sum = 0.0;
+        let mut weight_total = 0.0;
+        let mut weight = 1.0;
+        for score in self.performance_history.iter().rev() {
+            weighted_sum += score * weight;
+            weight_total += weight;
+            weight *= discount;
+        }
+        weighted_sum / weight_total * self.performance_history.len() as f64
     }
 
     pub fn current_policy(&self) -> Arc<dyn Policy> {
         self.current_policy.clone()
     }
 
+    /// Borrows the current policy without cloning the `Arc`.
+    pub fn policy(&self) -> &Arc<dyn Policy> {
+        &self.current_policy
+    }
+
+    /// Predicts from `history` as perceived by this agent, i.e. shifted by its `perception_bias`
+    /// and clamped to [0, 1].
     pub fn decide(&mut self, history: &[f64]) -> f64 {
-        let prediction = self.current_policy.decide(history);
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    /// Same as [`Agent::decide`], also passing local information to the policy.
+    pub fn decide_with_context(&mut self, history: &[f64], context: &NeighborContext) -> f64 {
+        let prediction = if self.perception_bias == 0.0 {
+            self.current_policy.decide_with_context(history, context)
+        } else {
+            let perceived: Vec<f64> = history
+                .iter()
+                .map(|ratio| (ratio + self.perception_bias).clamp(0.0, 1.0))
+                .collect();
+            self.current_policy.decide_with_context(&perceived, context)
+        };
         self.last_prediction = Some(prediction);
         prediction
     }
 
-    pub fn adapt_strategy(&mut self, neighbors: &[(&Agent, f64)], temperature: f64, policy_retention_rate: f64) {
+    /// Possibly switches to a neighbor's policy. All randomness is drawn from `rng`, so runs are
+    /// reproducible for a seeded generator.
+    ///
+    /// If there is any candidate neighbor, the RNG is consumed in a fixed order: one `f64` for
+    /// the retention check, then the draws of the selection. The selection is made even if the
+    /// policy is retained, so the stream consumed per agent does not depend on
+    /// `policy_retention_rate` and a seeded run with retention only differs from one without it
+    /// where a policy was actually retained. Without candidates nothing is drawn.
+    ///
+    /// `distance_weights`, if given, holds one prior weight per neighbor that multiplies its
+    /// softmax weight (ignored by greedy selection).
+    ///
+    /// `uphill_from`, if given, is the agent's own performance: only neighbors strictly better
+    /// than it are considered, and the policy is retained if there are none.
+    pub fn adapt_strategy(
+        &mut self,
+        neighbors: &[(&Agent, f64)],
+        distance_weights: Option<&[f64]>,
+        uphill_from: Option<f64>,
+        temperature: f64,
+        policy_retention_rate: f64,
+        rng: &mut impl Rng,
+    ) {
+        let uphill;
+        let (neighbors, distance_weights) = match uphill_from {
+            Some(own_performance) => {
+                uphill = uphill_neighbors(neighbors, distance_weights, own_performance);
+                (uphill.0.as_slice(), uphill.1.as_deref())
+            }
+            None => (neighbors, distance_weights),
+        };
+
         if neighbors.is_empty() {
             return;
         }
 
-        let mut rng = rand::thread_rng();
-        if rng.gen::<f64>() < policy_retention_rate {
+        let retain = rng.gen::<f64>() < policy_retention_rate;
+        let new_policy = self.choose_new_policy(neighbors, distance_weights, temperature, rng);
+        if retain {
             return;
         }
 
-        let new_policy = self.choose_new_policy(neighbors, temperature, &mut rng);
-        
-        if self.current_policy.name() != new_policy.name() {
-            self.current_policy = new_policy;
-            self.performance_history.clear();
-        } else {
-            self.current_policy = new_policy;
+        // Agents share stateless policy instances, so comparing pointers usually avoids building
+        // names. An agent keeping its strategy also keeps its instance and with it any state.
+        let same_policy =
+            Arc::ptr_eq(&self.current_policy, &new_policy) || self.current_policy.name() == new_policy.name();
+        if !same_policy {
+            self.current_policy = instance_for_agent(&new_policy);
+            self.clear_performance_history();
         }
     }
     
-    fn choose_new_policy(&self, neighbors: &[(&Agent, f64)], temperature: f64, rng: &mut impl Rng) -> Arc<dyn Policy> {
+    fn choose_new_policy(
+        &self,
+        neighbors: &[(&Agent, f64)],
+        distance_weights: Option<&[f64]>,
+        temperature: f64,
+        rng: &mut impl Rng,
+    ) -> Arc<dyn Policy> {
         let performances: Vec<f64> = neighbors.iter().map(|(_, perf)| *perf).collect();
-    
-        if temperature < 1e-6 {
-            self.greedy_policy_selection(neighbors, &performances, rng)
-        } else {
-            self.softmax_policy_selection(neighbors, &performances, temperature, rng)
-        }
-    }
-    
-    fn greedy_policy_selection(&self, neighbors: &[(&Agent, f64)], performances: &[f64], rng: &mut impl Rng) -> Arc<dyn Policy> {
-        let max_perf = performances.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-    
-        let best_indices: Vec<usize> = performances.iter().enumerate()
-            .filter(|(_, &perf)| (perf - max_perf).abs() < 1e-6)
-            .map(|(i, _)| i)
-            .collect();
-    
-        if let Some(&chosen_index) = best_indices.choose(rng) {
-            neighbors[chosen_index].0.current_policy()
+        let chosen_index = if temperature < 1e-6 {
+            greedy_selection(&performances, rng)
         } else {
-            // Fallback: This should ideally not be reached if neighbors is not empty.
-            // Return the policy of a random neighbor.
-            neighbors.choose(rng).unwrap().0.current_policy()
-        }
-    }
-    
-    fn softmax_policy_selection(&self, neighbors: &[(&Agent, f64)], performances: &[f64], temperature: f64, rng: &mut impl Rng) -> Arc<dyn Policy> {
-        let max_perf = performances.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-    
-        let weights: Vec<f64> = performances.iter()
-            .map(|&perf| ((perf - max_perf) / temperature).exp())
-            .collect();
-    
-        let dist = match rand::distributions::WeightedIndex::new(&weights) {
-            Ok(dist) => dist,
-            Err(_) => { // This can happen if all weights are zero (e.g., due to underflow)
-                // Fallback to uniform random selection among neighbors
-                let dist = rand::distributions::Uniform::new(0, neighbors.len());
-                let chosen_index = rng.sample(dist);
-                return neighbors[chosen_index].0.current_policy();
-            }
+            weighted_softmax_selection(&performances, distance_weights, temperature, rng)
         };
-    
-        let chosen_index = rng.sample(dist);
         neighbors[chosen_index].0.current_policy()
     }
 
     pub fn clear_performance_history(&mut self) {
         self.performance_history.clear();
+        self.performance_cache = PerformanceCache::default();
+    }
+}
+
+/// Keeps the neighbors (and their distance weights) performing strictly better than `own_performance`.
+fn uphill_neighbors<'a>(
+    neighbors: &[(&'a Agent, f64)],
+    distance_weights: Option<&[f64]>,
+    own_performance: f64,
+) -> (Vec<(&'a Agent, f64)>, Option<Vec<f64>>) {
+    let better: Vec<usize> = (0..neighbors.len())
+        .filter(|&k| neighbors[k].1 > own_performance)
+        .collect();
+    (
+        better.iter().map(|&k| neighbors[k]).collect(),
+        distance_weights.map(|weights| better.iter().map(|&k| weights[k]).collect()),
+    )
+}
+
+/// Picks the index of the neighbor whose policy is adopted, given the neighbors' performances.
+///
+/// Temperatures below 1e-6 select greedily among the best performers, anything else samples from
+/// the softmax distribution. `performances` must not be empty.
+pub fn select_policy_index(performances: &[f64], temperature: f64, rng: &mut impl Rng) -> usize {
+    if temperature < 1e-6 {
+        greedy_selection(performances, rng)
+    } else {
+        softmax_selection(performances, temperature, rng)
+    }
+}
+
+/// Uniformly picks one of the indices with maximal performance.
+pub fn greedy_selection(performances: &[f64], rng: &mut impl Rng) -> usize {
+    let max_perf = performances.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+
+    let best_indices: Vec<usize> = performances.iter().enumerate()
+        .filter(|(_, &perf)| (perf - max_perf).abs() < 1e-6)
+        .map(|(i, _)| i)
+        .collect();
+
+    if let Some(&chosen_index) = best_indices.choose(rng) {
+        chosen_index
+    } else {
+        // Fallback: This should ideally not be reached if performances is not empty
+        // (only happens for NaN performances). Pick a random index.
+        rng.gen_range(0..performances.len())
+    }
+}
+
+/// Samples an index with probability proportional to `exp((perf - max_perf) / temperature)`.
+pub fn softmax_selection(performances: &[f64], temperature: f64, rng: &mut impl Rng) -> usize {
+    weighted_softmax_selection(performances, None, temperature, rng)
+}
+
+/// Like [`softmax_selection`], with every softmax weight multiplied by the matching prior weight.
+pub fn weighted_softmax_selection(
+    performances: &[f64],
+    prior_weights: Option<&[f64]>,
+    temperature: f64,
+    rng: &mut impl Rng,
+) -> usize {
+    // Shifting by the best performance among candidates with a non-zero prior keeps every
+    // exponent at or below 0, so no weight overflows and at least one weight stays non-zero
+    // however small the temperature
+    let max_perf = performances
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| prior_weights.is_none_or(|w| w[i] > 0.0))
+        .fold(f64::NEG_INFINITY, |a, (_, &b)| a.max(b));
+
+    let weights: Vec<f64> = performances.iter()
+        .enumerate()
+        .map(|(i, &perf)| {
+            let prior = prior_weights.map_or(1.0, |w| w[i]);
+            if prior <= 0.0 {
+                // Could lie above `max_perf`, where exp would overflow and 0 * inf give NaN
+                return 0.0;
+            }
+            // The best performer gets exponent 0 even where the division would give NaN
+            // (0 / 0 at temperature 0, or infinite performances)
+            let exponent = if perf == max_perf { 0.0 } else { (perf - max_perf) / temperature };
+            prior * exponent.exp()
+        })
+        .collect();
+
+    match rand::distributions::WeightedIndex::new(&weights) {
+        Ok(dist) => rng.sample(dist),
+        Err(_) => {
+            // This can happen if all weights are zero (e.g., due to underflow)
+            // Fallback to uniform random selection among neighbors
+            rng.sample(rand::distributions::Uniform::new(0, performances.len()))
+        }
     }
 }
 
+/// The clone gets its own copy of a stateful policy, see [`instance_for_agent`].
 impl Clone for Agent {
     fn clone(&self) -> Self {
         Self {
-            current_policy: self.current_policy.clone(),
+            current_policy: instance_for_agent(&self.current_policy),
             performance_history: self.performance_history.clone(),
             last_prediction: self.last_prediction,
+            perception_bias: self.perception_bias,
+            performance_cache: self.performance_cache.clone(),
         }
     }
 }
@@ -135,38 +310,250 @@ impl Clone for Agent {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::super::policy::{AlwaysGo, NeverGo};
+    use super::super::game::DEFAULT_THRESHOLD;
+    use super::super::policy::{
+        AlwaysGo, LocalHerdPolicy, NeverGo, PredictFromYesterday, RandomPolicy, SmoothedSelfPolicy,
+    };
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
     #[test]
     fn test_agent_performance() {
         let mut agent = Agent::new(Arc::new(AlwaysGo));
 
-        // Scenario 1: Agent goes to a non-crowded bar (2 points)
+        // Scenario 1: Agent goes to a non-crowded bar (1 point)
         // Agent predicts 0.0, so `went_to_bar` will be true.
         // Actual attendance is 0.2, so `bar_is_overcrowded` is false.
-        agent.update_performance(true, 0.2);
-        assert_eq!(agent.performance_history, vec![2.0]);
-        assert!((agent.performance() - 2.0).abs() < 1e-9);
+        agent.update_performance(true, 0.2, DEFAULT_THRESHOLD);
+        assert_eq!(agent.performance_history, vec![1.0]);
+        assert!((agent.performance() - 1.0).abs() < 1e-9);
 
         // Scenario 2: Agent stays home from a crowded bar (1 point)
         // Agent predicts 0.7, so `went_to_bar` will be false.
         // Actual attendance is 0.7, so `bar_is_overcrowded` is true.
-        agent.update_performance(false, 0.7);
-        assert_eq!(agent.performance_history, vec![2.0, 1.0]);
-        assert!((agent.performance() - 3.0).abs() < 1e-9);
+        agent.update_performance(false, 0.7, DEFAULT_THRESHOLD);
+        assert_eq!(agent.performance_history, vec![1.0, 1.0]);
+        assert!((agent.performance() - 2.0).abs() < 1e-9);
 
         // Scenario 3: Agent goes to a crowded bar (0 points)
-        agent.update_performance(true, 0.8);
-        assert_eq!(agent.performance_history, vec![2.0, 1.0, 0.0]);
-        assert!((agent.performance() - 3.0).abs() < 1e-9);
+        agent.update_performance(true, 0.8, DEFAULT_THRESHOLD);
+        assert_eq!(agent.performance_history, vec![1.0, 1.0, 0.0]);
+        assert!((agent.performance() - 2.0).abs() < 1e-9);
 
         // Scenario 4: Agent stays home from a non-crowded bar (0 points)
-        agent.update_performance(false, 0.3);
-        assert_eq!(agent.performance_history, vec![2.0, 1.0, 0.0, 0.0]);
-        assert!((agent.performance() - 3.0).abs() < 1e-9);
+        agent.update_performance(false, 0.3, DEFAULT_THRESHOLD);
+        assert_eq!(agent.performance_history, vec![1.0, 1.0, 0.0, 0.0]);
+        assert!((agent.performance() - 2.0).abs() < 1e-9);
 
         // Test with empty history
         let agent_no_history = Agent::new(Arc::new(NeverGo));
         assert!((agent_no_history.performance() - 0.0).abs() < 1e-9);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_policy_selection_is_seed_stable() {
+        let neighbors_agents = [
+            Agent::new(Arc::new(AlwaysGo)),
+            Agent::new(Arc::new(NeverGo)),
+            Agent::new(Arc::new(PredictFromYesterday)),
+            Agent::new(Arc::new(RandomPolicy)),
+        ];
+        let performances = [3.0, 3.0, 1.0, 2.0];
+        let neighbors: Vec<(&Agent, f64)> = neighbors_agents.iter().zip(performances).collect();
+        let agent = Agent::new(Arc::new(AlwaysGo));
+
+        for temperature in [0.0, 1.0] {
+            for seed in 0..20 {
+                let mut rng_a = StdRng::seed_from_u64(seed);
+                let mut rng_b = StdRng::seed_from_u64(seed);
+                let choice_a = agent.choose_new_policy(&neighbors, None, temperature, &mut rng_a);
+                let choice_b = agent.choose_new_policy(&neighbors, None, temperature, &mut rng_b);
+                assert_eq!(choice_a.name(), choice_b.name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_discounted_performance() {
+        let mut agent = Agent::new(Arc::new(AlwaysGo));
+        agent.set_performance_history(vec![1.0, 0.0, 1.0]);
+
+        assert!((agent.discounted_performance(1.0) - agent.performance()).abs() < 1e-9);
+
+        // Most recent first: weights 1, 0.5, 0.25
+        let expected = (1.0 * 1.0 + 0.0 * 0.5 + 1.0 * 0.25) / 1.75 * 3.0;
+        assert!((agent.discounted_performance(0.5) - expected).abs() < 1e-9);
+
+        // Recent failure weighs more than an old one
+        agent.set_performance_history(vec![1.0, 1.0, 0.0]);
+        let recent_failure = agent.discounted_performance(0.5);
+        agent.set_performance_history(vec![0.0, 1.0, 1.0]);
+        assert!(recent_failure < agent.discounted_performance(0.5));
+    }
+
+    #[test]
+    fn test_softmax_selection_uniform_performances() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut counts = [0usize; 4];
+        for _ in 0..20_000 {
+            counts[softmax_selection(&[2.0, 2.0, 2.0, 2.0], 1.0, &mut rng)] += 1;
+        }
+        for count in counts {
+            assert!((4_500..5_500).contains(&count), "counts: {:?}", counts);
+        }
+    }
+
+    #[test]
+    fn test_softmax_selection_dominant_performer_at_low_temperature() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let picks_of_best = (0..1_000)
+            .filter(|_| softmax_selection(&[1.0, 5.0, 2.0], 0.1, &mut rng) == 1)
+            .count();
+        assert!(picks_of_best >= 999);
+
+        assert_eq!(select_policy_index(&[1.0, 5.0, 2.0], 0.0, &mut rng), 1);
+    }
+
+    #[test]
+    fn test_uphill_only_retains_without_better_neighbor() {
+        let neighbors_agents = [Agent::new(Arc::new(NeverGo)), Agent::new(Arc::new(RandomPolicy))];
+        let neighbors: Vec<(&Agent, f64)> = neighbors_agents.iter().zip([2.0, 3.0]).collect();
+
+        for seed in 0..20 {
+            let mut agent = Agent::new(Arc::new(AlwaysGo));
+            let mut rng = StdRng::seed_from_u64(seed);
+            agent.adapt_strategy(&neighbors, None, Some(3.0), 1.0, 0.0, &mut rng);
+            assert_eq!(agent.current_policy().name(), "Always Go");
+        }
+
+        // Only the strictly better neighbor qualifies
+        let mut agent = Agent::new(Arc::new(AlwaysGo));
+        agent.adapt_strategy(&neighbors, None, Some(2.5), 1.0, 0.0, &mut StdRng::seed_from_u64(0));
+        assert_eq!(agent.current_policy().name(), "Random");
+    }
+
+    #[test]
+    fn test_perception_bias_shifts_history() {
+        let mut agent = Agent::new(Arc::new(PredictFromYesterday));
+        assert_eq!(agent.decide(&[0.1, 0.3]), 0.3);
+        assert_eq!(agent.last_prediction, Some(0.3));
+
+        agent.perception_bias = 0.2;
+        assert!((agent.decide(&[0.1, 0.3]) - 0.5).abs() < 1e-12);
+        agent.perception_bias = 0.9;
+        assert_eq!(agent.decide(&[0.1, 0.3]), 1.0);
+    }
+
+    #[test]
+    fn test_softmax_tiny_temperature_stays_finite() {
+        let performances = [0.0, 1e6, -1e9, 5.0];
+        let mut rng = StdRng::seed_from_u64(2);
+        for temperature in [1e-8, 0.0] {
+            for _ in 0..50 {
+                assert_eq!(softmax_selection(&performances, temperature, &mut rng), 1);
+            }
+        }
+
+        // With the best performer weighted out, the runner-up wins instead of every weight
+        // underflowing to zero and falling back to a uniform choice
+        let priors = [1.0, 0.0, 1.0, 1.0];
+        for _ in 0..50 {
+            assert_eq!(weighted_softmax_selection(&performances, Some(&priors), 1e-8, &mut rng), 3);
+        }
+    }
+
+    #[test]
+    fn test_performance_is_memoized_until_dirty() {
+        let mut agent = Agent::new(Arc::new(AlwaysGo));
+        agent.record_score(1.0);
+        agent.record_score(1.0);
+        assert_eq!(agent.performance(), 2.0);
+        assert_eq!(agent.discounted_performance(0.5), 2.0);
+
+        // Bypassing the dirty flag shows the cached values are returned
+        agent.performance_history.push(1.0);
+        assert_eq!(agent.performance(), 2.0);
+        assert_eq!(agent.discounted_performance(0.5), 2.0);
+
+        // Recording a score marks the agent dirty, so both are recomputed from all four rounds
+        agent.record_score(0.0);
+        assert_eq!(agent.performance(), 3.0);
+        let expected = (0.0 + 1.0 * 0.5 + 1.0 * 0.25 + 1.0 * 0.125) / 1.875 * 4.0;
+        assert!((agent.discounted_performance(0.5) - expected).abs() < 1e-9);
+
+        agent.clear_performance_history();
+        assert_eq!(agent.performance(), 0.0);
+        assert_eq!(agent.discounted_performance(0.5), 0.0);
+    }
+
+    #[test]
+    fn test_adapt_strategy_rng_consumption_order() {
+        let neighbors_agents = [
+            Agent::new(Arc::new(AlwaysGo)),
+            Agent::new(Arc::new(NeverGo)),
+            Agent::new(Arc::new(RandomPolicy)),
+        ];
+        let neighbors: Vec<(&Agent, f64)> = neighbors_agents.iter().zip([1.0, 1.5, 0.5]).collect();
+
+        for seed in 0..20 {
+            // One f64 for the retention check, then the softmax selection
+            let mut replay = StdRng::seed_from_u64(seed);
+            let retain = replay.gen::<f64>() < 0.3;
+            let index = weighted_softmax_selection(&[1.0, 1.5, 0.5], None, 1.0, &mut replay);
+            let expected = if retain {
+                "Predict from yesterday".to_string()
+            } else {
+                neighbors_agents[index].current_policy().name()
+            };
+
+            let mut agent = Agent::new(Arc::new(PredictFromYesterday));
+            let mut rng = StdRng::seed_from_u64(seed);
+            agent.adapt_strategy(&neighbors, None, None, 1.0, 0.3, &mut rng);
+            assert_eq!(agent.current_policy().name(), expected);
+            assert_eq!(rng.gen::<u64>(), replay.gen::<u64>());
+        }
+    }
+
+    #[test]
+    fn test_retention_does_not_shift_rng_stream() {
+        let neighbors_agents = [Agent::new(Arc::new(AlwaysGo)), Agent::new(Arc::new(NeverGo))];
+        let neighbors: Vec<(&Agent, f64)> = neighbors_agents.iter().zip([1.0, 1.0]).collect();
+
+        for seed in 0..20 {
+            let mut always_retain = StdRng::seed_from_u64(seed);
+            let mut never_retain = StdRng::seed_from_u64(seed);
+            let mut retained = Agent::new(Arc::new(RandomPolicy));
+            let mut switched = Agent::new(Arc::new(RandomPolicy));
+            retained.adapt_strategy(&neighbors, None, None, 1.0, 1.0, &mut always_retain);
+            switched.adapt_strategy(&neighbors, None, None, 1.0, 0.0, &mut never_retain);
+
+            assert_eq!(retained.current_policy().name(), "Random");
+            assert_ne!(switched.current_policy().name(), "Random");
+            assert_eq!(always_retain.gen::<u64>(), never_retain.gen::<u64>());
+        }
+    }
+
+    #[test]
+    fn test_agents_keep_own_policy_state() {
+        // Smoothing a neighbor-dependent prediction: with a shared instance the second agent's
+        // prediction would overwrite the first one's remembered prediction
+        let policy: Arc<dyn Policy> =
+            Arc::new(SmoothedSelfPolicy::new(Arc::new(LocalHerdPolicy::new(1.0)), 0.5));
+        let mut first = Agent::new(policy.clone());
+        let mut second = Agent::new(policy);
+        let context = |went, round| NeighborContext {
+            neighbors_went: Some(went),
+            round: Some(round),
+            ..NeighborContext::default()
+        };
+        assert_eq!(first.decide_with_context(&[0.5], &context(1.0, 1)), 1.0);
+        assert_eq!(second.decide_with_context(&[0.5], &context(0.0, 1)), 0.0);
+        assert_eq!(first.decide_with_context(&[0.5, 0.5], &context(0.0, 2)), 0.5);
+        assert_eq!(second.decide_with_context(&[0.5, 0.5], &context(0.0, 2)), 0.0);
+
+        // Clones carry the state over, but no longer share it
+        let mut clone = first.clone();
+        assert_eq!(clone.decide_with_context(&[0.5, 0.5, 0.5], &context(1.0, 3)), 0.75);
+        assert_eq!(first.decide_with_context(&[0.5, 0.5, 0.5], &context(0.0, 3)), 0.25);
+    }
+}
@@ -1,36 +1,216 @@
 use super::agent::Agent;
-use ndarray::Array2;
+use super::policy::NeighborContext;
+use ndarray::{Array2, Zip};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Attendance ratio at or above which the bar counts as overcrowded.
+pub const DEFAULT_THRESHOLD: f64 = 0.6;
+
+/// Payoff agents receive for their go/stay decision each round, which drives strategy adaptation.
+///
+/// Both modes reward the same side: going when attendance ends below the threshold and staying
+/// home when it ends above. They differ in what losing costs and in how a round ending exactly at
+/// the threshold is scored.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameMode {
+    /// 1 point for the right decision, 0 otherwise. Attendance exactly at the threshold counts
+    /// as crowded, so staying home wins.
+    #[default]
+    ElFarol,
+    /// Minority game: agents on the minority side of the threshold get +1, the majority side
+    /// gets -1. Attendance exactly at the threshold leaves no minority and scores 0 for everyone.
+    /// The penalty doubles the score gap between winning and losing policies in the adaptation softmax.
+    MinorityGame,
+}
+
+impl GameMode {
+    /// Score of a single agent's decision given the round's attendance ratio.
+    pub fn score(self, went_to_bar: bool, attendance_ratio: f64, threshold: f64) -> f64 {
+        match self {
+            GameMode::ElFarol => {
+                let bar_is_overcrowded = attendance_ratio >= threshold;
+                match (went_to_bar, bar_is_overcrowded) {
+                    (true, false) => 1.0, // Went to a non-crowded bar
+                    (false, true) => 1.0, // Stayed home from a crowded bar
+                    _ => 0.0,             // Other cases
+                }
+            }
+            GameMode::MinorityGame => {
+                if attendance_ratio == threshold {
+                    0.0
+                } else if went_to_bar == (attendance_ratio < threshold) {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+        }
+    }
+}
 
 pub struct Game {
     grid: Array2<Agent>,
     pub history: Vec<f64>,
+    threshold: f64,
+    softness: Option<f64>,
+    go_if_below: bool,
+    max_history: Option<usize>,
+    /// Rounds played so far, passed to policies as [`NeighborContext::round`] since the history
+    /// length stops growing once it is capped
+    rounds_played: usize,
+    /// Passed to policies as [`NeighborContext::seed`]
+    seed: u64,
+    regret_fraction: f64,
+    decision_churn: f64,
+    mode: GameMode,
+    /// Flat indices (row-major) of every agent's neighbors, excluding the agent itself
+    neighborhoods: Option<Vec<Vec<usize>>>,
+    /// Every agent's decision in the last round, row-major; empty before the first round
+    last_decisions: Vec<bool>,
+    /// Scratch buffers reused by every round instead of allocating new ones: the agents'
+    /// predictions and the decisions of the round being played
+    predictions: Array2<f64>,
+    decisions: Vec<bool>,
+}
+
+/// Decides whether an agent with the given prediction goes to the bar.
+///
+/// Without `softness` the agent goes iff `prediction < threshold`. With it, the agent goes with
+/// probability `1 / (1 + exp(-(threshold - prediction) / softness))`, which approaches the hard
+/// rule as `softness` goes to zero.
+pub fn decide_to_go(prediction: f64, threshold: f64, softness: Option<f64>, rng: &mut impl Rng) -> bool {
+    match softness {
+        Some(softness) if softness > 0.0 => {
+            let go_probability = 1.0 / (1.0 + (-(threshold - prediction) / softness).exp());
+            rng.gen::<f64>() < go_probability
+        }
+        _ => prediction < threshold,
+    }
+}
+
+/// Fraction of the neighbors of agent `index` that went to the bar last round.
+fn neighbors_went(neighborhoods: &[Vec<usize>], last_decisions: &[bool], index: usize) -> Option<f64> {
+    neighborhoods
+        .get(index)
+        .filter(|neighbors| !neighbors.is_empty() && !last_decisions.is_empty())
+        .map(|neighbors| {
+            let went = neighbors
+                .iter()
+                .filter(|&&neighbor| last_decisions.get(neighbor).copied().unwrap_or(false))
+                .count();
+            went as f64 / neighbors.len() as f64
+        })
 }
 
 impl Game {
-    pub fn new(grid: Array2<Agent>) -> Self {
+    pub fn new(grid: Array2<Agent>, threshold: f64) -> Self {
         Self {
+            predictions: Array2::zeros(grid.dim()),
             grid,
             history: Vec::new(),
+            threshold,
+            softness: None,
+            go_if_below: true,
+            max_history: None,
+            rounds_played: 0,
+            seed: 0,
+            regret_fraction: 0.0,
+            decision_churn: 0.0,
+            mode: GameMode::default(),
+            neighborhoods: None,
+            last_decisions: Vec::new(),
+            decisions: Vec::new(),
         }
     }
 
-    pub fn run(&mut self) -> f64 {
+    /// Keeps only the last `max_history` attendance ratios. Policies then only see this window,
+    /// e.g. "Full History Average" becomes an average over the last `max_history` rounds. Stateful
+    /// policies still advance every round, as they count rounds with [`NeighborContext::round`].
+    pub fn set_max_history(&mut self, max_history: Option<usize>) {
+        self.max_history = max_history;
+        self.trim_history();
+    }
+
+    fn trim_history(&mut self) {
+        if let Some(max_history) = self.max_history {
+            let excess = self.history.len().saturating_sub(max_history);
+            self.history.drain(..excess);
+        }
+    }
+
+    /// Seed from which policies derive their random draws, see
+    /// [`agent_round_rng`](super::policy::agent_round_rng).
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    pub fn set_mode(&mut self, mode: GameMode) {
+        self.mode = mode;
+    }
+
+    /// Sets the neighbors (flat row-major indices, excluding the agent itself) whose last
+    /// decisions are passed to policies as [`NeighborContext`]. `None` passes no local information.
+    pub fn set_neighborhoods(&mut self, neighborhoods: Option<Vec<Vec<usize>>>) {
+        self.neighborhoods = neighborhoods;
+    }
+
+    /// Makes the go/no-go decision probabilistic, see [`decide_to_go`]. `None` restores the hard rule.
+    pub fn set_softness(&mut self, softness: Option<f64>) {
+        self.softness = softness;
+    }
+
+    /// With `false` agents go iff `prediction >= threshold` (or, with softness, with the
+    /// complementary probability). Only the decision flips; the game mode still rewards going to
+    /// an uncrowded bar, and policy names such as "Always Go" describe the default direction.
+    pub fn set_go_if_below(&mut self, go_if_below: bool) {
+        self.go_if_below = go_if_below;
+    }
+
+    /// Plays one round and returns the attendance ratio.
+    ///
+    /// Predictions are computed in parallel with rayon. Deterministic policies give the same
+    /// result as a sequential pass, including ones drawing from
+    /// [`agent_round_rng`](super::policy::agent_round_rng) such as
+    /// [`NoisyPolicy`](super::policy::NoisyPolicy); anything using `thread_rng` is not
+    /// reproducible across runs. Policies keeping state between rounds (behind a `Mutex`) would
+    /// advance it in scheduling order if agents shared an instance, so every agent predicts with
+    /// its own, see [`instance_for_agent`](super::policy::instance_for_agent). The go decisions
+    /// themselves draw from `rng` in grid order.
+    pub fn run(&mut self, rng: &mut impl Rng) -> f64 {
         let total_agents = self.grid.len();
 
-        let predictions: Vec<f64> = self.grid.iter()
-            .map(|agent| agent.current_policy().decide(&self.history))
-            .collect();
+        // The grid may have been replaced by one of a different size
+        if self.predictions.dim() != self.grid.dim() {
+            self.predictions = Array2::zeros(self.grid.dim());
+        }
+        let history = &self.history;
+        let neighborhoods = self.neighborhoods.as_deref();
+        let last_decisions = &self.last_decisions;
+        let num_cols = self.grid.ncols();
+        let round = self.rounds_played;
+        let seed = self.seed;
+        let threshold = self.threshold;
+        Zip::indexed(&mut self.grid)
+            .and(&mut self.predictions)
+            .par_for_each(|(r, c), agent, prediction| {
+                let context = NeighborContext {
+                    neighbors_went: neighborhoods
+                        .and_then(|neighborhoods| neighbors_went(neighborhoods, last_decisions, r * num_cols + c)),
+                    round: Some(round),
+                    agent: r * num_cols + c,
+                    seed,
+                    threshold: Some(threshold),
+                };
+                *prediction = agent.decide_with_context(history, &context);
+            });
 
-        let mut attendance = 0;
-        let mut went_to_bar_list = Vec::new();
-        for (agent, &prediction) in self.grid.iter_mut().zip(predictions.iter()) {
-            agent.last_prediction = Some(prediction);
-            let went_to_bar = prediction < 0.6;
-            went_to_bar_list.push(went_to_bar);
-            if went_to_bar {
-                attendance += 1;
-            }
+        self.decisions.clear();
+        for &prediction in self.predictions.iter() {
+            self.decisions
+                .push(decide_to_go(prediction, self.threshold, self.softness, rng) == self.go_if_below);
         }
+        let attendance = self.decisions.iter().filter(|&&went_to_bar| went_to_bar).count();
 
         // Calculate actual attendance ratio
         let actual_attendance_ratio = if total_agents > 0 {
@@ -39,16 +219,60 @@ impl Game {
             0.0
         };
 
-        // Update agent performances based on their prediction accuracy
-        for (i, agent) in self.grid.iter_mut().enumerate() {
-            agent.update_performance(went_to_bar_list[i], actual_attendance_ratio);
+        // An agent regrets going to a crowded bar or staying home from an uncrowded one
+        let bar_is_overcrowded = actual_attendance_ratio >= self.threshold;
+        let regretting = self
+            .decisions
+            .iter()
+            .filter(|&&went_to_bar| went_to_bar == bar_is_overcrowded)
+            .count();
+        self.regret_fraction = regretting as f64 / total_agents.max(1) as f64;
+
+        // Score every agent's decision according to the game mode
+        for (agent, &went_to_bar) in self.grid.iter_mut().zip(&self.decisions) {
+            agent.record_score(self.mode.score(went_to_bar, actual_attendance_ratio, self.threshold));
         }
 
+        let flipped = self
+            .last_decisions
+            .iter()
+            .zip(&self.decisions)
+            .filter(|(previous, current)| previous != current)
+            .count();
+        self.decision_churn = flipped as f64 / total_agents.max(1) as f64;
+        std::mem::swap(&mut self.last_decisions, &mut self.decisions);
+
         // Record game result
         self.history.push(actual_attendance_ratio);
+        self.trim_history();
+        self.rounds_played += 1;
         actual_attendance_ratio
     }
 
+    /// Attendance ratios of all rounds played so far (or the last `max_history` of them).
+    pub fn history(&self) -> &[f64] {
+        &self.history
+    }
+
+    /// Fraction of agents that made the wrong choice in the last round.
+    pub fn regret_fraction(&self) -> f64 {
+        self.regret_fraction
+    }
+
+    /// Fraction of agents whose go/no-go decision differs from the previous round (0.0 in the
+    /// first round).
+    pub fn decision_churn(&self) -> f64 {
+        self.decision_churn
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    pub fn set_threshold(&mut self, threshold: f64) {
+        self.threshold = threshold;
+    }
+
     pub fn get_grid(&self) -> &Array2<Agent> {
         &self.grid
     }
@@ -57,3 +281,231 @@ impl Game {
         self.grid = new_grid;
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation_logic::policy::{
+        AlwaysGo, ConstantPolicy, LocalHerdPolicy, NeverGo, PidPolicy, Policy, PredictFromYesterday,
+    };
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_soft_decision_approaches_hard_decision() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for &prediction in &[0.0, 0.3, 0.59, 0.61, 0.9, 1.0] {
+            let hard = decide_to_go(prediction, 0.6, None, &mut rng);
+            for _ in 0..100 {
+                assert_eq!(decide_to_go(prediction, 0.6, Some(1e-6), &mut rng), hard);
+            }
+        }
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let grid = Array2::from_shape_fn((3, 3), |(r, _)| {
+            if r == 0 {
+                Agent::new(std::sync::Arc::new(AlwaysGo))
+            } else {
+                Agent::new(std::sync::Arc::new(NeverGo))
+            }
+        });
+        let mut game = Game::new(grid, DEFAULT_THRESHOLD);
+        game.set_max_history(Some(5));
+        let mut rng = StdRng::seed_from_u64(0);
+        for round in 1..=20 {
+            game.run(&mut rng);
+            assert_eq!(game.history.len(), round.min(5));
+        }
+    }
+
+    #[test]
+    fn test_go_if_below_flips_decisions() {
+        // Three agents predict an empty bar, six a full one
+        let grid = Array2::from_shape_fn((3, 3), |(r, _)| {
+            if r == 0 {
+                Agent::new(std::sync::Arc::new(AlwaysGo))
+            } else {
+                Agent::new(std::sync::Arc::new(NeverGo))
+            }
+        });
+        let mut game = Game::new(grid, DEFAULT_THRESHOLD);
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!((game.run(&mut rng) - 3.0 / 9.0).abs() < 1e-12);
+
+        game.set_go_if_below(false);
+        assert!((game.run(&mut rng) - 6.0 / 9.0).abs() < 1e-12);
+        // A prediction exactly at the threshold goes under the flipped rule
+        game.set_grid(Array2::from_elem((1, 1), Agent::new(std::sync::Arc::new(ConstantPolicy::new(DEFAULT_THRESHOLD)))));
+        assert_eq!(game.run(&mut rng), 1.0);
+        game.set_go_if_below(true);
+        assert_eq!(game.run(&mut rng), 0.0);
+    }
+
+    #[test]
+    fn test_rounds_are_unchanged() {
+        // Golden of a soft-decision game with neighbor context; scratch buffers reused between
+        // rounds must not change any result
+        let policies: Vec<std::sync::Arc<dyn Policy>> = vec![
+            std::sync::Arc::new(LocalHerdPolicy::new(0.5)),
+            std::sync::Arc::new(PredictFromYesterday),
+            std::sync::Arc::new(ConstantPolicy::new(0.55)),
+        ];
+        let grid = Array2::from_shape_fn((4, 4), |(r, c)| Agent::new(policies[(r * 4 + c) % 3].clone()));
+        let mut game = Game::new(grid, DEFAULT_THRESHOLD);
+        game.history = vec![0.4];
+        game.set_softness(Some(0.1));
+        game.set_neighborhoods(Some(
+            (0..16).map(|i| vec![(i + 1) % 16, (i + 4) % 16]).collect(),
+        ));
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut churn = Vec::new();
+        for _ in 0..8 {
+            game.run(&mut rng);
+            churn.push(game.decision_churn());
+        }
+        assert_eq!(game.history(), &[0.4, 0.875, 0.4375, 0.75, 0.3125, 0.875, 0.125, 0.9375, 0.25]);
+        assert_eq!(churn, vec![0.0, 0.5625, 0.5625, 0.5625, 0.6875, 0.75, 0.9375, 0.8125]);
+        assert_eq!(game.regret_fraction(), 0.75);
+    }
+
+    #[test]
+    fn test_regret_fraction() {
+        // Three of nine agents go: the bar is not crowded, so the six who stayed home regret it
+        let grid = Array2::from_shape_fn((3, 3), |(r, _)| {
+            if r == 0 {
+                Agent::new(std::sync::Arc::new(AlwaysGo))
+            } else {
+                Agent::new(std::sync::Arc::new(NeverGo))
+            }
+        });
+        let mut game = Game::new(grid, DEFAULT_THRESHOLD);
+        let mut rng = StdRng::seed_from_u64(0);
+        game.run(&mut rng);
+        assert!((game.regret_fraction() - 6.0 / 9.0).abs() < 1e-12);
+
+        // At a threshold of 1/3 the bar counts as crowded, so only the three who went regret it
+        game.set_threshold(1.0 / 3.0);
+        game.run(&mut rng);
+        assert!((game.regret_fraction() - 3.0 / 9.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_minority_game_goers_win_below_threshold() {
+        assert_eq!(GameMode::MinorityGame.score(true, 0.4, 0.6), 1.0);
+        assert_eq!(GameMode::MinorityGame.score(false, 0.4, 0.6), -1.0);
+        assert_eq!(GameMode::ElFarol.score(true, 0.4, 0.6), 1.0);
+        assert_eq!(GameMode::ElFarol.score(false, 0.4, 0.6), 0.0);
+
+        // Three of nine agents go and are the minority
+        let grid = Array2::from_shape_fn((3, 3), |(r, _)| {
+            if r == 0 {
+                Agent::new(std::sync::Arc::new(AlwaysGo))
+            } else {
+                Agent::new(std::sync::Arc::new(NeverGo))
+            }
+        });
+        let mut game = Game::new(grid, 0.5);
+        game.set_mode(GameMode::MinorityGame);
+        game.run(&mut StdRng::seed_from_u64(0));
+        assert_eq!(game.get_grid()[[0, 0]].performance_history(), [1.0]);
+        assert_eq!(game.get_grid()[[2, 2]].performance_history(), [-1.0]);
+    }
+
+    #[test]
+    fn test_minority_game_stayers_win_above_threshold() {
+        assert_eq!(GameMode::MinorityGame.score(false, 0.8, 0.6), 1.0);
+        assert_eq!(GameMode::MinorityGame.score(true, 0.8, 0.6), -1.0);
+        assert_eq!(GameMode::ElFarol.score(false, 0.8, 0.6), 1.0);
+        assert_eq!(GameMode::ElFarol.score(true, 0.8, 0.6), 0.0);
+
+        // Exactly at the threshold El Farol counts the bar as crowded, the minority game has no winner
+        assert_eq!(GameMode::ElFarol.score(false, 0.6, 0.6), 1.0);
+        assert_eq!(GameMode::MinorityGame.score(false, 0.6, 0.6), 0.0);
+        assert_eq!(GameMode::MinorityGame.score(true, 0.6, 0.6), 0.0);
+    }
+
+    #[test]
+    fn test_local_herd_sees_neighbor_decisions() {
+        use crate::simulation_logic::policy::LocalHerdPolicy;
+
+        // A herding agent in the middle of eight agents that always go
+        let grid = Array2::from_shape_fn((3, 3), |pos| {
+            if pos == (1, 1) {
+                Agent::new(std::sync::Arc::new(LocalHerdPolicy::new(0.5)))
+            } else {
+                Agent::new(std::sync::Arc::new(AlwaysGo))
+            }
+        });
+        let mut game = Game::new(grid, DEFAULT_THRESHOLD);
+        game.set_neighborhoods(Some(
+            (0..9).map(|i| (0..9).filter(|&j| j != i).collect()).collect(),
+        ));
+        game.history.push(0.2);
+        let mut rng = StdRng::seed_from_u64(0);
+        game.run(&mut rng);
+        game.history = vec![0.2];
+        game.run(&mut rng);
+        assert!((game.get_grid()[[1, 1]].last_prediction.unwrap() - 0.6).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_decision_churn_counts_flips() {
+        // Everyone predicts yesterday's ratio: 0.2 sends everybody, 0.8 keeps everybody home
+        let grid = Array2::from_elem((2, 2), Agent::new(std::sync::Arc::new(PredictFromYesterday)));
+        let mut game = Game::new(grid, DEFAULT_THRESHOLD);
+        let mut rng = StdRng::seed_from_u64(0);
+        game.history = vec![0.2];
+        game.run(&mut rng);
+        assert_eq!(game.decision_churn(), 0.0);
+        game.history = vec![0.8];
+        game.run(&mut rng);
+        assert_eq!(game.decision_churn(), 1.0);
+    }
+
+    /// Predicts the round it is asked about, in hundredths.
+    #[derive(Debug)]
+    struct RoundEcho;
+
+    impl Policy for RoundEcho {
+        fn decide(&self, history: &[f64]) -> f64 {
+            self.decide_with_context(history, &NeighborContext::default())
+        }
+
+        fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+            context.current_round(history) as f64 / 100.0
+        }
+
+        fn name(&self) -> String {
+            "Round Echo".to_string()
+        }
+    }
+
+    #[test]
+    fn test_rounds_keep_counting_with_capped_history() {
+        let grid = Array2::from_elem((1, 1), Agent::new(std::sync::Arc::new(RoundEcho)));
+        let mut game = Game::new(grid, DEFAULT_THRESHOLD);
+        game.set_max_history(Some(2));
+        let mut rng = StdRng::seed_from_u64(0);
+        for round in 0..6 {
+            game.run(&mut rng);
+            assert_eq!(game.get_grid()[[0, 0]].last_prediction, Some(round as f64 / 100.0));
+        }
+        assert_eq!(game.history.len(), 2);
+    }
+
+    #[test]
+    fn test_pid_keeps_steering_with_capped_history() {
+        // A pure integral controller alternates between going and staying home; with the state
+        // keyed on the history length it froze once the history stopped growing
+        let grid = Array2::from_elem((1, 1), Agent::new(std::sync::Arc::new(PidPolicy::new(0.0, 1.0, 0.0))));
+        let mut game = Game::new(grid, DEFAULT_THRESHOLD);
+        game.set_max_history(Some(3));
+        let mut rng = StdRng::seed_from_u64(0);
+        let attendance: Vec<f64> = (0..12).map(|_| game.run(&mut rng)).collect();
+        assert!(attendance[6..].contains(&0.0));
+        assert!(attendance[6..].contains(&1.0));
+    }
+}
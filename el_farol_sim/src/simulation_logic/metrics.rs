@@ -0,0 +1,92 @@
+//! Post-run metrics computed from recorded series.
+
+use crate::StrategyId;
+use ndarray::Array2;
+use std::collections::HashMap;
+
+/// Sample autocorrelation of the attendance series at `lag`:
+/// `Σ (x_t - m)(x_{t+lag} - m) / Σ (x_t - m)²` with `m` the series mean.
+///
+/// Strongly negative values at lag 1 indicate the period-2 alternation typical of El Farol.
+/// Returns 0.0 for a constant series or if `lag` is not shorter than the series.
+pub fn attendance_autocorr(attendance: &[f64], lag: usize) -> f64 {
+    if lag >= attendance.len() {
+        return 0.0;
+    }
+    let mean = attendance.iter().sum::<f64>() / attendance.len() as f64;
+    let variance: f64 = attendance.iter().map(|x| (x - mean).powi(2)).sum();
+    if variance == 0.0 {
+        return 0.0;
+    }
+    let covariance: f64 = attendance
+        .iter()
+        .zip(&attendance[lag..])
+        .map(|(x, y)| (x - mean) * (y - mean))
+        .sum();
+    covariance / variance
+}
+
+/// Shannon entropy (in bits) of the strategy distribution on the grid; 0.0 for a monoculture and
+/// `log2(k)` for `k` equally common strategies.
+pub fn strategy_entropy(policy_ids: &Array2<StrategyId>) -> f64 {
+    let mut counts: HashMap<StrategyId, usize> = HashMap::new();
+    for &id in policy_ids.iter() {
+        *counts.entry(id).or_default() += 1;
+    }
+    let total = policy_ids.len() as f64;
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Fraction of cells whose policy id is the same in `previous` and `current`; 1.0 means the grid
+/// did not change at all. Returns 1.0 for empty grids.
+pub fn grid_stability(previous: &Array2<StrategyId>, current: &Array2<StrategyId>) -> f64 {
+    assert_eq!(previous.dim(), current.dim(), "Grid stability needs grids of the same shape");
+    if current.is_empty() {
+        return 1.0;
+    }
+    let unchanged = previous.iter().zip(current.iter()).filter(|(a, b)| a == b).count();
+    unchanged as f64 / current.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alternating_series() {
+        let alternating: Vec<f64> = (0..100).map(|t| if t % 2 == 0 { 0.3 } else { 0.8 }).collect();
+        assert!(attendance_autocorr(&alternating, 1) < -0.9);
+        assert!(attendance_autocorr(&alternating, 2) > 0.9);
+    }
+
+    #[test]
+    fn test_degenerate_series() {
+        assert_eq!(attendance_autocorr(&[0.5; 10], 1), 0.0);
+        assert_eq!(attendance_autocorr(&[0.1, 0.9], 2), 0.0);
+        assert_eq!(attendance_autocorr(&[], 0), 0.0);
+    }
+
+    #[test]
+    fn test_strategy_entropy() {
+        assert_eq!(strategy_entropy(&Array2::zeros((4, 4))), 0.0);
+        let four_way = Array2::from_shape_fn((4, 4), |(r, c)| (2 * (r % 2) + c % 2) as StrategyId);
+        assert!((strategy_entropy(&four_way) - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_grid_stability() {
+        let grid = Array2::from_shape_fn((3, 3), |(r, c)| ((r + c) % 2) as StrategyId);
+        assert_eq!(grid_stability(&grid, &grid.clone()), 1.0);
+        let mut changed = grid.clone();
+        changed[[0, 0]] = 5;
+        changed[[2, 1]] = 5;
+        assert!((grid_stability(&grid, &changed) - 7.0 / 9.0).abs() < 1e-12);
+        assert_eq!(grid_stability(&Array2::zeros((0, 0)), &Array2::zeros((0, 0))), 1.0);
+    }
+}
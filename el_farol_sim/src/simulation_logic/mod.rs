@@ -1,4 +1,6 @@
 pub mod agent;
 pub mod game;
+pub mod metrics;
+pub mod multi_bar;
 pub mod policy;
 pub mod simulation; 
\ No newline at end of file
@@ -0,0 +1,167 @@
+//! El Farol with several bars, each with its own comfort threshold.
+//!
+//! Every agent applies its policy to the attendance history of each bar, giving one prediction
+//! per bar, and goes to the bar it expects to be least crowded relative to its threshold. If it
+//! expects every bar to be overcrowded it stays home, so the single-bar case reduces to the
+//! hard `prediction < threshold` rule of [`Game`](super::game::Game).
+
+use super::policy::{instance_for_agent, NeighborContext, Policy};
+use ndarray::Array2;
+use std::sync::Arc;
+
+pub struct MultiBarGame {
+    /// Every agent's policy, with one instance per bar (see [`instance_for_agent`]) so that
+    /// policies keeping state between rounds track each bar separately
+    policies: Array2<Vec<Arc<dyn Policy>>>,
+    thresholds: Vec<f64>,
+    /// Attendance ratio history per bar
+    histories: Vec<Vec<f64>>,
+    /// Bar every agent went to in the last round, `None` for staying home
+    last_choices: Array2<Option<usize>>,
+}
+
+/// Index of the bar with the largest margin `threshold - prediction`, or `None` if no bar is
+/// predicted to stay below its threshold. Ties go to the lower index.
+pub fn choose_bar(predictions: &[f64], thresholds: &[f64]) -> Option<usize> {
+    predictions
+        .iter()
+        .zip(thresholds)
+        .map(|(prediction, threshold)| threshold - prediction)
+        .enumerate()
+        .filter(|&(_, margin)| margin > 0.0)
+        .fold(None, |best: Option<(usize, f64)>, (bar, margin)| match best {
+            Some((_, best_margin)) if best_margin >= margin => best,
+            _ => Some((bar, margin)),
+        })
+        .map(|(bar, _)| bar)
+}
+
+impl MultiBarGame {
+    /// Panics if there are no bars.
+    pub fn new(policies: Array2<Arc<dyn Policy>>, thresholds: Vec<f64>) -> Self {
+        assert!(!thresholds.is_empty(), "A multi-bar game needs at least one bar");
+        let last_choices = Array2::from_elem(policies.dim(), None);
+        let policies = policies.map(|policy| thresholds.iter().map(|_| instance_for_agent(policy)).collect());
+        Self {
+            histories: vec![Vec::new(); thresholds.len()],
+            policies,
+            thresholds,
+            last_choices,
+        }
+    }
+
+    /// Plays one round and returns the attendance ratio of every bar, which is also appended to
+    /// the bars' histories. The ratios are fractions of all agents, so they sum to at most 1.
+    ///
+    /// Policies see the row-major agent index in their [`NeighborContext`] and the bar's index as
+    /// its seed, so random draws differ between agents and bars.
+    pub fn run(&mut self) -> Vec<f64> {
+        let histories = &self.histories;
+        let thresholds = &self.thresholds;
+        let num_cols = self.policies.ncols();
+        self.last_choices = Array2::from_shape_fn(self.policies.dim(), |(r, c)| {
+            let predictions: Vec<f64> = self.policies[[r, c]]
+                .iter()
+                .zip(histories)
+                .enumerate()
+                .map(|(bar, (policy, history))| {
+                    let context = NeighborContext {
+                        agent: r * num_cols + c,
+                        seed: bar as u64,
+                        threshold: Some(thresholds[bar]),
+                        ..NeighborContext::default()
+                    };
+                    policy.decide_with_context(history, &context)
+                })
+                .collect();
+            choose_bar(&predictions, thresholds)
+        });
+
+        let mut attendance = vec![0usize; self.thresholds.len()];
+        for bar in self.last_choices.iter().flatten() {
+            attendance[*bar] += 1;
+        }
+        let num_agents = self.policies.len().max(1) as f64;
+        let ratios: Vec<f64> = attendance.into_iter().map(|count| count as f64 / num_agents).collect();
+        for (history, &ratio) in self.histories.iter_mut().zip(&ratios) {
+            history.push(ratio);
+        }
+        ratios
+    }
+
+    pub fn thresholds(&self) -> &[f64] {
+        &self.thresholds
+    }
+
+    /// Attendance ratio history of every bar.
+    pub fn histories(&self) -> &[Vec<f64>] {
+        &self.histories
+    }
+
+    /// Bar every agent went to in the last round, `None` for staying home.
+    pub fn last_choices(&self) -> &Array2<Option<usize>> {
+        &self.last_choices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation_logic::policy::{AlwaysGo, ConfidenceWeightedPolicy, NeverGo};
+
+    /// Predicts the bar's last attendance ratio, 0.0 before the first round.
+    #[derive(Debug)]
+    struct LastOrZero;
+
+    impl Policy for LastOrZero {
+        fn decide(&self, history: &[f64]) -> f64 {
+            history.last().copied().unwrap_or(0.0)
+        }
+
+        fn name(&self) -> String {
+            "Last or Zero".to_string()
+        }
+    }
+
+    #[test]
+    fn test_choose_bar() {
+        assert_eq!(choose_bar(&[0.5, 0.2], &[0.6, 0.6]), Some(1));
+        assert_eq!(choose_bar(&[0.3, 0.3], &[0.6, 0.6]), Some(0));
+        assert_eq!(choose_bar(&[0.7, 0.6], &[0.6, 0.6]), None);
+    }
+
+    #[test]
+    fn test_two_bar_attendance() {
+        let policies: Vec<Arc<dyn Policy>> =
+            vec![Arc::new(AlwaysGo), Arc::new(AlwaysGo), Arc::new(NeverGo), Arc::new(LastOrZero)];
+        let policies = Array2::from_shape_vec((2, 2), policies).unwrap();
+        let mut game = MultiBarGame::new(policies, vec![0.6, 0.6]);
+
+        // Everyone willing to go expects two empty bars and picks the first
+        assert_eq!(game.run(), vec![0.75, 0.0]);
+        assert_eq!(game.last_choices()[[1, 0]], None);
+
+        // The history-based agent now expects the first bar to be crowded and switches
+        assert_eq!(game.run(), vec![0.5, 0.25]);
+        assert_eq!(game.last_choices()[[1, 1]], Some(1));
+        assert_eq!(game.histories(), &[vec![0.75, 0.5], vec![0.0, 0.25]]);
+    }
+
+    #[test]
+    fn test_stateful_policy_tracks_every_bar() {
+        let policies: Vec<Arc<dyn Policy>> = vec![
+            Arc::new(AlwaysGo),
+            Arc::new(AlwaysGo),
+            Arc::new(AlwaysGo),
+            Arc::new(ConfidenceWeightedPolicy::new(Arc::new(LastOrZero))),
+        ];
+        let policies = Array2::from_shape_vec((1, 4), policies).unwrap();
+        let mut game = MultiBarGame::new(policies, vec![0.6, 0.6]);
+        assert_eq!(game.run(), vec![1.0, 0.0]);
+
+        // Predicting an empty first bar was badly wrong, the empty second bar was right, so the
+        // hedging agent trusts its prediction for the second bar only
+        assert_eq!(game.run(), vec![0.75, 0.25]);
+        assert_eq!(game.last_choices()[[0, 3]], Some(1));
+    }
+}
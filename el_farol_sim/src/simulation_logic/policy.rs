@@ -1,14 +1,110 @@
+use super::game::DEFAULT_THRESHOLD;
+use crate::ElFarolError;
 use std::fmt::Debug;
 use rand::distributions::{Distribution, Uniform};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 
+/// Information beyond the attendance history passed to [`Policy::decide_with_context`]: the
+/// agent's surroundings and the round being played.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NeighborContext {
+    /// Fraction of the agent's neighbors that went to the bar last round; `None` before the first
+    /// round or for an agent without neighbors
+    pub neighbors_went: Option<f64>,
+    /// Number of rounds played before this one. Unlike the history length it keeps growing once
+    /// the game caps the history, see `Game::set_max_history`; `None` if the caller does not
+    /// count rounds.
+    pub round: Option<usize>,
+    /// Row-major index of the deciding agent on the grid
+    pub agent: usize,
+    /// Seed of the run, for policies that draw random numbers, see [`agent_round_rng`]
+    pub seed: u64,
+    /// Comfort threshold of the round, for policies steering toward it; `None` if the caller has
+    /// none, in which case they use [`DEFAULT_THRESHOLD`]
+    pub threshold: Option<f64>,
+}
+
+impl NeighborContext {
+    /// The round being decided, or the history length if the caller did not pass it.
+    pub fn current_round(&self, history: &[f64]) -> usize {
+        self.round.unwrap_or(history.len())
+    }
+
+    /// The comfort threshold of the round, or [`DEFAULT_THRESHOLD`] if the caller did not pass it.
+    pub fn current_threshold(&self) -> f64 {
+        self.threshold.unwrap_or(DEFAULT_THRESHOLD)
+    }
+}
+
+/// Generator for the random draws of one agent in one round, derived from the run's seed, the
+/// round, the agent and `policy_seed`. Agents decide in parallel, so the draws must not depend on
+/// a generator shared between calls.
+pub fn agent_round_rng(context: &NeighborContext, history: &[f64], policy_seed: u64) -> StdRng {
+    let parts = [context.seed, policy_seed, context.current_round(history) as u64, context.agent as u64];
+    StdRng::seed_from_u64(parts.into_iter().fold(0, |hash, part| splitmix64(hash ^ part)))
+}
+
+/// Finalizer of the SplitMix64 generator, mixing all bits of `x`.
+fn splitmix64(x: u64) -> u64 {
+    let x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Prediction for an empty history, drawn from [`agent_round_rng`] so that it is reproducible
+/// for a seeded run.
+pub fn random_guess(context: &NeighborContext) -> f64 {
+    agent_round_rng(context, &[], 0).gen()
+}
+
 /// Trait defining the behavior of a policy
 pub trait Policy: Send + Sync + Debug {
     /// Makes a prediction of bar attendance ratio (0.0-1.0) based on the history of past games
     fn decide(&self, history: &[f64]) -> f64;
+
+    /// Makes a prediction that may also use local information about the agent's neighbors.
+    /// Policies that only look at the global history keep the default, which ignores `context`;
+    /// policies drawing random numbers, even if only for a guess on an empty history, draw them
+    /// from [`agent_round_rng`].
+    /// Policies keeping state between rounds advance it on [`NeighborContext::current_round`],
+    /// since a capped history stops growing, and wrappers pass `context` on to their inner policy.
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        let _ = context;
+        self.decide(history)
+    }
     
     /// Returns a name for the policy
     fn name(&self) -> String;
+
+    /// Serializable description of the policy and its parameters, see [`PolicySpec::build`].
+    /// Policies without a variant of their own are described by their name.
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::Named(self.name())
+    }
+
+    /// False if predictions draw on randomness not derived from the run's seed (see
+    /// [`agent_round_rng`]), so a seeded run using the policy is not reproducible.
+    fn is_deterministic(&self) -> bool {
+        true
+    }
+
+    /// For policies keeping state between rounds, a copy (including the current state) whose
+    /// state is independent of this instance's; `None` for policies without such state. Agents
+    /// decide in parallel, so each agent predicts with its own copy, see [`instance_for_agent`].
+    fn copy_with_state(&self) -> Option<Arc<dyn Policy>> {
+        None
+    }
+}
+
+/// The instance an agent predicts with: `policy` itself if it keeps no state between rounds,
+/// otherwise a copy of its own, so that its state does not depend on the order in which agents
+/// sharing the policy decide.
+pub fn instance_for_agent(policy: &Arc<dyn Policy>) -> Arc<dyn Policy> {
+    policy.copy_with_state().unwrap_or_else(|| policy.clone())
 }
 
 /// Always goes to the bar
@@ -23,6 +119,10 @@ impl Policy for AlwaysGo {
     fn name(&self) -> String {
         "Always Go".to_string()
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::AlwaysGo
+    }
 }
 
 /// Never goes to the bar
@@ -37,6 +137,37 @@ impl Policy for NeverGo {
     fn name(&self) -> String {
         "Never Go".to_string()
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::NeverGo
+    }
+}
+
+/// Always predicts the same fixed ratio, e.g. as a baseline forecaster
+#[derive(Debug, Clone, Copy)]
+pub struct ConstantPolicy {
+    value: f64,
+}
+
+impl ConstantPolicy {
+    pub fn new(value: f64) -> Self {
+        assert!((0.0..=1.0).contains(&value));
+        Self { value }
+    }
+}
+
+impl Policy for ConstantPolicy {
+    fn decide(&self, _history: &[f64]) -> f64 {
+        self.value
+    }
+
+    fn name(&self) -> String {
+        format!("Constant ({})", self.value)
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::Constant { value: self.value }
+    }
 }
 
 /// Predicts attendance will be the same as yesterday
@@ -45,16 +176,24 @@ pub struct PredictFromYesterday;
 
 impl Policy for PredictFromYesterday {
     fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
         if let Some(last_ratio) = history.last() {
             *last_ratio
         } else {
-            rand::random::<f64>()
+            random_guess(context)
         }
     }
 
     fn name(&self) -> String {
         "Predict from yesterday".to_string()
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::PredictFromYesterday
+    }
 }
 
 /// Predicts attendance will be the same as the day before yesterday
@@ -63,12 +202,20 @@ pub struct PredictFromDayBeforeYesterday;
 
 impl Policy for PredictFromDayBeforeYesterday {
     fn decide(&self, history: &[f64]) -> f64 {
-        history.iter().rev().nth(1).or(history.last()).copied().unwrap_or(rand::random::<f64>())
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        history.iter().rev().nth(1).or(history.last()).copied().unwrap_or(random_guess(context))
     }
 
     fn name(&self) -> String {
         "Predict from day before yesterday".to_string()
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::PredictFromDayBeforeYesterday
+    }
 }
 
 /// Random decision
@@ -83,6 +230,14 @@ impl Policy for RandomPolicy {
     fn name(&self) -> String {
         "Random".to_string()
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::Random
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
 }
 
 /// Moving average based decision
@@ -91,15 +246,19 @@ pub struct MovingAveragePolicy<const WINDOW_SIZE: usize>;
 
 impl<const WINDOW_SIZE: usize> Policy for MovingAveragePolicy<WINDOW_SIZE> {
     fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
         if history.is_empty() || WINDOW_SIZE == 0 {
-            return rand::random::<f64>();
+            return random_guess(context);
         }
 
         let start = history.len().saturating_sub(WINDOW_SIZE);
         let relevant_history = &history[start..];
 
         if relevant_history.is_empty() {
-            return rand::random::<f64>();
+            return random_guess(context);
         }
 
         let sum: f64 = relevant_history.iter().sum();
@@ -109,6 +268,10 @@ impl<const WINDOW_SIZE: usize> Policy for MovingAveragePolicy<WINDOW_SIZE> {
     fn name(&self) -> String {
         format!("Moving Average ({})", WINDOW_SIZE)
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::MovingAverage { window: WINDOW_SIZE }
+    }
 }
 
 /// Predicts attendance will be the average of all past attendances
@@ -117,8 +280,12 @@ pub struct FullHistoryAveragePolicy;
 
 impl Policy for FullHistoryAveragePolicy {
     fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
         if history.is_empty() {
-            return rand::random::<f64>();
+            return random_guess(context);
         }
         history.iter().sum::<f64>() / history.len() as f64
     }
@@ -126,6 +293,10 @@ impl Policy for FullHistoryAveragePolicy {
     fn name(&self) -> String {
         "Full History Average".to_string()
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::FullHistoryAverage
+    }
 }
 
 /// Predicts attendance will be the average of past attendances on even days
@@ -134,9 +305,13 @@ pub struct EvenHistoryAveragePolicy;
 
 impl Policy for EvenHistoryAveragePolicy {
     fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
         let even_day_history: Vec<f64> = history.iter().step_by(2).copied().collect();
         if even_day_history.is_empty() {
-            rand::random::<f64>() 
+            random_guess(context)
         } else {
             even_day_history.iter().sum::<f64>() / even_day_history.len() as f64
         }
@@ -145,6 +320,10 @@ impl Policy for EvenHistoryAveragePolicy {
     fn name(&self) -> String {
         "Even History Average".to_string()
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::EvenHistoryAverage
+    }
 }
 
 /// Complex formula: 1/2[sqrt(1/2(b_n^2+b_(n-1)^2)) + b_(n-2)]
@@ -166,6 +345,10 @@ impl Policy for ComplexFormulaPolicy {
     fn name(&self) -> String {
         "Complex Formula".to_string()
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::ComplexFormula
+    }
 }
 
 /// Drunkard: Average of b_0, ... b_n and subtract 0.05
@@ -174,8 +357,12 @@ pub struct DrunkardPolicy;
 
 impl Policy for DrunkardPolicy {
     fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
         let avg = if history.is_empty() {
-            rand::random::<f64>() 
+            random_guess(context)
         } else {
             history.iter().sum::<f64>() / history.len() as f64
         };
@@ -185,6 +372,10 @@ impl Policy for DrunkardPolicy {
     fn name(&self) -> String {
         "Drunkard".to_string()
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::Drunkard
+    }
 }
 
 /// Stupid Nerd: Like drunkard, but instead add 0.05
@@ -193,8 +384,12 @@ pub struct StupidNerdPolicy;
 
 impl Policy for StupidNerdPolicy {
     fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
         let avg = if history.is_empty() {
-            rand::random::<f64>() 
+            random_guess(context)
         } else {
             history.iter().sum::<f64>() / history.len() as f64
         };
@@ -204,6 +399,10 @@ impl Policy for StupidNerdPolicy {
     fn name(&self) -> String {
         "Stupid Nerd".to_string()
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::StupidNerd
+    }
 }
 
 /// Predicts a random ratio from a uniform distribution U(low, high)
@@ -230,6 +429,14 @@ impl Policy for UniformPolicy {
     fn name(&self) -> String {
         format!("Uniform [{}..{})", self.low, self.high)
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::Uniform { low: self.low, high: self.high }
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
 }
 
 /// Weighted average of history. Weights are iid Unif([0,2]) generated at start of game.
@@ -265,8 +472,12 @@ impl Clone for WeightedHistoryPolicy {
 
 impl Policy for WeightedHistoryPolicy {
     fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
         if history.is_empty() {
-            return rand::random::<f64>();
+            return random_guess(context);
         }
 
         let mut weights = self.weights.lock().unwrap();
@@ -292,6 +503,14 @@ impl Policy for WeightedHistoryPolicy {
     fn name(&self) -> String {
         "Weighted History".to_string()
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::WeightedHistory
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
 }
 
 /// Sliding weighted average with 5 random weights
@@ -320,8 +539,12 @@ impl Default for SlidingWeightedAveragePolicy {
 
 impl Policy for SlidingWeightedAveragePolicy {
     fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
         if history.is_empty() {
-            return rand::random::<f64>();
+            return random_guess(context);
         }
 
         let window = &history[history.len().saturating_sub(5)..];
@@ -333,7 +556,7 @@ impl Policy for SlidingWeightedAveragePolicy {
             .sum();
 
         if window.is_empty() {
-            rand::random::<f64>()
+            random_guess(context)
         } else {
             weighted_sum / (window.len() as f64)
         }
@@ -342,6 +565,14 @@ impl Policy for SlidingWeightedAveragePolicy {
     fn name(&self) -> String {
         "Sliding Weighted Average (5)".to_string()
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::SlidingWeightedAverage
+    }
+
+    fn is_deterministic(&self) -> bool {
+        false
+    }
 }
 
 /// Exponentially weighted moving average
@@ -359,8 +590,12 @@ impl ExponentialMovingAveragePolicy {
 
 impl Policy for ExponentialMovingAveragePolicy {
     fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
         if history.is_empty() {
-            return rand::random::<f64>();
+            return random_guess(context);
         }
 
         let n = history.len() - 1;
@@ -385,6 +620,10 @@ impl Policy for ExponentialMovingAveragePolicy {
     fn name(&self) -> String {
         format!("Exponential Moving Average (a={})", self.alpha)
     }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::ExponentialMovingAverage { alpha: self.alpha }
+    }
 }
 
 /// Generalized sliding window mean
@@ -402,8 +641,12 @@ impl<const M: usize> GeneralizedMeanPolicy<M> {
 
 impl<const M: usize> Policy for GeneralizedMeanPolicy<M> {
     fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
         if history.is_empty() {
-            return rand::random::<f64>();
+            return random_guess(context);
         }
 
         let n = history.len();
@@ -411,7 +654,7 @@ impl<const M: usize> Policy for GeneralizedMeanPolicy<M> {
         let window = &history[n - window_size..];
 
         if window.is_empty() {
-            return rand::random::<f64>();
+            return random_guess(context);
         }
 
         let sum_of_powers: f64 = window.iter().map(|b| b.powf(self.r)).sum();
@@ -423,4 +666,2003 @@ impl<const M: usize> Policy for GeneralizedMeanPolicy<M> {
     fn name(&self) -> String {
         format!("Generalized Mean (m={}, r={})", M, self.r)
     }
-} 
\ No newline at end of file
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::GeneralizedMean { window: M, r: self.r }
+    }
+}
+
+/// Predicts the comfort threshold itself, perturbed by U(-jitter, jitter) drawn from
+/// [`agent_round_rng`].
+///
+/// Built with [`TargetThresholdPolicy::new`] it follows the threshold of the game it plays (see
+/// [`NeighborContext::threshold`]), with [`TargetThresholdPolicy::with_threshold`] it predicts
+/// a fixed one, which its name records.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetThresholdPolicy {
+    threshold: Option<f64>,
+    jitter: f64,
+}
+
+impl TargetThresholdPolicy {
+    pub fn new(jitter: f64) -> Self {
+        assert!(jitter >= 0.0);
+        Self { threshold: None, jitter }
+    }
+
+    pub fn with_threshold(threshold: f64, jitter: f64) -> Self {
+        assert!((0.0..=1.0).contains(&threshold));
+        Self { threshold: Some(threshold), ..Self::new(jitter) }
+    }
+}
+
+impl Policy for TargetThresholdPolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        let noise = if self.jitter > 0.0 {
+            agent_round_rng(context, history, 0).gen_range(-self.jitter..=self.jitter)
+        } else {
+            0.0
+        };
+        (self.threshold.unwrap_or_else(|| context.current_threshold()) + noise).clamp(0.0, 1.0)
+    }
+
+    fn name(&self) -> String {
+        match self.threshold {
+            Some(threshold) => format!("Target Threshold ({}, t={})", self.jitter, threshold),
+            None => format!("Target Threshold ({})", self.jitter),
+        }
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::TargetThreshold { threshold: self.threshold, jitter: self.jitter }
+    }
+}
+
+/// Exponential moving average pulled toward the comfort threshold:
+/// `ema * (1 - pull) + threshold * pull`, for agents who expect attendance to revert to capacity
+///
+/// Like [`TargetThresholdPolicy`] it follows the game's threshold unless built with
+/// [`MeanRevertingEmaPolicy::with_threshold`].
+#[derive(Debug, Clone)]
+pub struct MeanRevertingEmaPolicy {
+    ema: ExponentialMovingAveragePolicy,
+    threshold: Option<f64>,
+    pull: f64,
+}
+
+impl MeanRevertingEmaPolicy {
+    pub fn new(alpha: f64, pull: f64) -> Self {
+        assert!((0.0..=1.0).contains(&pull));
+        Self {
+            ema: ExponentialMovingAveragePolicy::new(alpha),
+            threshold: None,
+            pull,
+        }
+    }
+
+    pub fn with_threshold(threshold: f64, alpha: f64, pull: f64) -> Self {
+        assert!((0.0..=1.0).contains(&threshold));
+        Self { threshold: Some(threshold), ..Self::new(alpha, pull) }
+    }
+}
+
+impl Policy for MeanRevertingEmaPolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        let threshold = self.threshold.unwrap_or_else(|| context.current_threshold());
+        self.ema.decide_with_context(history, context) * (1.0 - self.pull) + threshold * self.pull
+    }
+
+    fn name(&self) -> String {
+        match self.threshold {
+            Some(threshold) => {
+                format!("Mean Reverting EMA (a={}, pull={}, t={})", self.ema.alpha, self.pull, threshold)
+            }
+            None => format!("Mean Reverting EMA (a={}, pull={})", self.ema.alpha, self.pull),
+        }
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::MeanRevertingEma {
+            threshold: self.threshold,
+            alpha: self.ema.alpha,
+            pull: self.pull,
+        }
+    }
+}
+
+/// Blends the inner policy's prediction with its own previous output:
+/// `momentum * last_prediction + (1 - momentum) * inner.decide(history)`.
+///
+/// The remembered prediction is the one made for the previous round; repeated calls within a
+/// round see the same state. Every agent remembers its own prediction, see
+/// [`instance_for_agent`].
+#[derive(Debug)]
+pub struct SmoothedSelfPolicy {
+    inner: Arc<dyn Policy>,
+    momentum: f64,
+    state: Mutex<SmoothedSelfState>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct SmoothedSelfState {
+    /// Round of the latest prediction, see [`NeighborContext::current_round`]
+    round: usize,
+    previous: Option<f64>,
+    current: Option<f64>,
+}
+
+impl SmoothedSelfPolicy {
+    pub fn new(inner: Arc<dyn Policy>, momentum: f64) -> Self {
+        assert!((0.0..=1.0).contains(&momentum));
+        Self {
+            inner,
+            momentum,
+            state: Mutex::new(SmoothedSelfState::default()),
+        }
+    }
+}
+
+impl Clone for SmoothedSelfPolicy {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            momentum: self.momentum,
+            state: Mutex::new(self.state.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl Policy for SmoothedSelfPolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        let round = context.current_round(history);
+        let mut state = self.state.lock().unwrap();
+        if state.round != round {
+            state.round = round;
+            state.previous = state.current;
+        }
+
+        let inner_prediction = self.inner.decide_with_context(history, context);
+        let prediction = match state.previous {
+            Some(previous) => self.momentum * previous + (1.0 - self.momentum) * inner_prediction,
+            None => inner_prediction,
+        };
+        state.current = Some(prediction);
+        prediction
+    }
+
+    fn name(&self) -> String {
+        format!("Smoothed Self ({}, m={})", self.inner.name(), self.momentum)
+    }
+
+    fn copy_with_state(&self) -> Option<Arc<dyn Policy>> {
+        let mut copy = self.clone();
+        copy.inner = instance_for_agent(&self.inner);
+        Some(Arc::new(copy))
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::SmoothedSelf {
+            inner: Box::new(self.inner.spec()),
+            momentum: self.momentum,
+        }
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.inner.is_deterministic()
+    }
+}
+
+/// Herds with the agent's neighbors: blends yesterday's global attendance ratio with the fraction
+/// of neighbors that went last round, `(1 - local_weight) * global + local_weight * local`.
+///
+/// Without neighbor information it falls back to [`PredictFromYesterday`].
+#[derive(Debug, Clone, Copy)]
+pub struct LocalHerdPolicy {
+    local_weight: f64,
+}
+
+impl LocalHerdPolicy {
+    pub fn new(local_weight: f64) -> Self {
+        assert!((0.0..=1.0).contains(&local_weight));
+        Self { local_weight }
+    }
+}
+
+impl Policy for LocalHerdPolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        PredictFromYesterday.decide(history)
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        match (history.last(), context.neighbors_went) {
+            (Some(global), Some(local)) => (1.0 - self.local_weight) * global + self.local_weight * local,
+            _ => PredictFromYesterday.decide_with_context(history, context),
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("Local Herd ({})", self.local_weight)
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::LocalHerd { local_weight: self.local_weight }
+    }
+}
+
+/// Fits a line to the log-odds of the last `window` attendance ratios and predicts the logistic
+/// of its extrapolation to the next round, so predictions stay strictly inside (0, 1).
+/// Ratios are clamped to `[EPSILON, 1 - EPSILON]` before taking the logit.
+#[derive(Debug, Clone, Copy)]
+pub struct LogisticTrendPolicy {
+    window: usize,
+}
+
+impl LogisticTrendPolicy {
+    const EPSILON: f64 = 1e-3;
+
+    pub fn new(window: usize) -> Self {
+        assert!(window > 0);
+        Self { window }
+    }
+}
+
+impl Policy for LogisticTrendPolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        if history.is_empty() {
+            return random_guess(context);
+        }
+
+        let recent = &history[history.len().saturating_sub(self.window)..];
+        let log_odds: Vec<f64> = recent
+            .iter()
+            .map(|ratio| {
+                let p = ratio.clamp(Self::EPSILON, 1.0 - Self::EPSILON);
+                (p / (1.0 - p)).ln()
+            })
+            .collect();
+
+        // Least squares fit of log_odds[x] = intercept + slope * x
+        let n = log_odds.len() as f64;
+        let x_mean = (n - 1.0) / 2.0;
+        let y_mean = log_odds.iter().sum::<f64>() / n;
+        let (mut covariance, mut x_variance) = (0.0, 0.0);
+        for (x, y) in log_odds.iter().enumerate() {
+            covariance += (x as f64 - x_mean) * (y - y_mean);
+            x_variance += (x as f64 - x_mean).powi(2);
+        }
+        let slope = if x_variance > 0.0 { covariance / x_variance } else { 0.0 };
+        let next_log_odds = y_mean + slope * (n - x_mean);
+        1.0 / (1.0 + (-next_log_odds).exp())
+    }
+
+    fn name(&self) -> String {
+        format!("Logistic Trend ({})", self.window)
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::LogisticTrend { window: self.window }
+    }
+}
+
+/// Exploits periodic attendance by averaging all past ratios at the same phase as the next round,
+/// i.e. `history[len - k * period]` for every k ≥ 1. Predicts 0.0 while the history is shorter
+/// than one period.
+#[derive(Debug, Clone, Copy)]
+pub struct PeriodicPolicy {
+    period: usize,
+}
+
+impl PeriodicPolicy {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0);
+        Self { period }
+    }
+}
+
+impl Policy for PeriodicPolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        let same_phase: Vec<f64> = (1..=history.len() / self.period)
+            .map(|k| history[history.len() - k * self.period])
+            .collect();
+        if same_phase.is_empty() {
+            return 0.0;
+        }
+        same_phase.iter().sum::<f64>() / same_phase.len() as f64
+    }
+
+    fn name(&self) -> String {
+        format!("Periodic ({})", self.period)
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::Periodic { period: self.period }
+    }
+}
+
+/// Nearest-neighbor forecaster: compares the last `window` ratios with every earlier stretch of
+/// `window` ratios (by L2 distance) and predicts the average of the ratios that followed the
+/// [`PatternMatchPolicy::NEIGHBORS`] closest stretches, preferring recent ones on ties.
+///
+/// Falls back to yesterday's ratio while there is no earlier stretch to compare with.
+#[derive(Debug, Clone, Copy)]
+pub struct PatternMatchPolicy {
+    window: usize,
+}
+
+impl PatternMatchPolicy {
+    /// Number of closest past stretches whose successors are averaged.
+    pub const NEIGHBORS: usize = 3;
+
+    pub fn new(window: usize) -> Self {
+        assert!(window > 0);
+        Self { window }
+    }
+}
+
+impl Policy for PatternMatchPolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        let Some(&last) = history.last() else {
+            return random_guess(context);
+        };
+        if history.len() <= self.window {
+            return last;
+        }
+
+        let current = &history[history.len() - self.window..];
+        // (distance, successor) of every stretch that has a successor, most recent first
+        let mut matches: Vec<(f64, f64)> = (0..history.len() - self.window)
+            .rev()
+            .map(|start| {
+                let distance = history[start..start + self.window]
+                    .iter()
+                    .zip(current)
+                    .map(|(a, b)| (a - b).powi(2))
+                    .sum::<f64>();
+                (distance, history[start + self.window])
+            })
+            .collect();
+        matches.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let nearest = &matches[..matches.len().min(Self::NEIGHBORS)];
+        nearest.iter().map(|&(_, successor)| successor).sum::<f64>() / nearest.len() as f64
+    }
+
+    fn name(&self) -> String {
+        format!("Pattern Match ({})", self.window)
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::PatternMatch { window: self.window }
+    }
+}
+
+/// Adds Gaussian observation noise N(0, sigma²) to the inner policy's prediction, clamped to [0, 1].
+///
+/// The noise is drawn from [`agent_round_rng`], so it depends only on the run's seed, the round
+/// and the agent, and seeded runs are reproducible whatever order the agents decide in. Noisy
+/// policies with different seeds (see [`NoisyPolicy::with_seed`]) draw independent noise.
+#[derive(Debug, Clone)]
+pub struct NoisyPolicy {
+    inner: Arc<dyn Policy>,
+    sigma: f64,
+    seed: u64,
+}
+
+impl NoisyPolicy {
+    pub fn new(inner: Arc<dyn Policy>, sigma: f64) -> Self {
+        Self::with_seed(inner, sigma, 0)
+    }
+
+    pub fn with_seed(inner: Arc<dyn Policy>, sigma: f64, seed: u64) -> Self {
+        assert!(sigma >= 0.0);
+        Self { inner, sigma, seed }
+    }
+}
+
+impl Policy for NoisyPolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        let prediction = self.inner.decide_with_context(history, context);
+        if self.sigma == 0.0 {
+            return prediction;
+        }
+        // Box–Muller transform; 1 - u keeps the logarithm's argument in (0, 1]
+        let mut rng = agent_round_rng(context, history, self.seed);
+        let (u1, u2): (f64, f64) = (1.0 - rng.gen::<f64>(), rng.gen());
+        let noise = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+        (prediction + self.sigma * noise).clamp(0.0, 1.0)
+    }
+
+    fn name(&self) -> String {
+        format!("Noisy({}, {})", self.inner.name(), self.sigma)
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::Noisy {
+            inner: Box::new(self.inner.spec()),
+            sigma: self.sigma,
+            seed: self.seed,
+        }
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.inner.is_deterministic()
+    }
+
+    fn copy_with_state(&self) -> Option<Arc<dyn Policy>> {
+        let inner = self.inner.copy_with_state()?;
+        Some(Arc::new(Self { inner, ..self.clone() }))
+    }
+}
+
+/// Limited, noisy recall: predicts the mean of `k` past ratios sampled without replacement, or of
+/// the whole history if it holds fewer than `k` ratios.
+///
+/// Like [`NoisyPolicy`] the samples are drawn from [`agent_round_rng`], see
+/// [`RandomSubsetAveragePolicy::with_seed`].
+#[derive(Debug, Clone, Copy)]
+pub struct RandomSubsetAveragePolicy {
+    k: usize,
+    seed: u64,
+}
+
+impl RandomSubsetAveragePolicy {
+    pub fn new(k: usize) -> Self {
+        Self::with_seed(k, 0)
+    }
+
+    pub fn with_seed(k: usize, seed: u64) -> Self {
+        assert!(k > 0);
+        Self { k, seed }
+    }
+}
+
+impl Policy for RandomSubsetAveragePolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        let mut rng = agent_round_rng(context, history, self.seed);
+        if history.is_empty() {
+            return rng.gen::<f64>();
+        }
+        if history.len() <= self.k {
+            return history.iter().sum::<f64>() / history.len() as f64;
+        }
+        let sample = rand::seq::index::sample(&mut rng, history.len(), self.k);
+        sample.iter().map(|i| history[i]).sum::<f64>() / self.k as f64
+    }
+
+    fn name(&self) -> String {
+        format!("Random Subset Avg ({})", self.k)
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::RandomSubsetAverage { k: self.k, seed: self.seed }
+    }
+}
+
+/// Ensemble forecaster predicting the weighted average of its members' predictions, with the
+/// weights normalized to sum to one.
+///
+/// The name only records the number of members, so two committees of the same size but with
+/// different members share a strategy id on the grid.
+#[derive(Debug, Clone)]
+pub struct CommitteePolicy {
+    members: Vec<(Arc<dyn Policy>, f64)>,
+}
+
+impl CommitteePolicy {
+    /// Panics if a weight is negative or not finite, or if all weights are zero.
+    pub fn new(members: Vec<(Arc<dyn Policy>, f64)>) -> Self {
+        assert!(
+            members.iter().all(|(_, weight)| weight.is_finite() && *weight >= 0.0),
+            "Committee weights must be finite and non-negative"
+        );
+        assert!(
+            members.iter().map(|(_, weight)| weight).sum::<f64>() > 0.0,
+            "Committee weights must not all be zero"
+        );
+        Self { members }
+    }
+}
+
+impl Policy for CommitteePolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        let total_weight: f64 = self.members.iter().map(|(_, weight)| weight).sum();
+        self.members
+            .iter()
+            .filter(|(_, weight)| *weight > 0.0)
+            .map(|(member, weight)| weight * member.decide_with_context(history, context))
+            .sum::<f64>()
+            / total_weight
+    }
+
+    fn name(&self) -> String {
+        format!("Committee({})", self.members.len())
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::Committee {
+            members: self.members.iter().map(|(member, weight)| (member.spec(), *weight)).collect(),
+        }
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.members.iter().all(|(member, _)| member.is_deterministic())
+    }
+
+    fn copy_with_state(&self) -> Option<Arc<dyn Policy>> {
+        let mut stateful = false;
+        let members = self
+            .members
+            .iter()
+            .map(|(member, weight)| match member.copy_with_state() {
+                Some(copy) => {
+                    stateful = true;
+                    (copy, *weight)
+                }
+                None => (member.clone(), *weight),
+            })
+            .collect();
+        stateful.then(|| Arc::new(Self { members }) as Arc<dyn Policy>)
+    }
+}
+
+/// Moving average whose window adapts to the volatility of recent attendance: the window shrinks
+/// by one each round the standard deviation of the last [`AdaptiveWindowPolicy::LOOKBACK`] ratios
+/// exceeds `volatility_threshold` and grows by one otherwise, staying within
+/// `min_window..=max_window`.
+///
+/// Like [`SmoothedSelfPolicy`] every agent keeps its own window, which only advances once per
+/// round.
+#[derive(Debug)]
+pub struct AdaptiveWindowPolicy {
+    min_window: usize,
+    max_window: usize,
+    volatility_threshold: f64,
+    state: Mutex<AdaptiveWindowState>,
+}
+
+#[derive(Debug, Clone)]
+struct AdaptiveWindowState {
+    /// Round of the latest prediction, see [`NeighborContext::current_round`]
+    round: usize,
+    window: usize,
+}
+
+impl AdaptiveWindowPolicy {
+    /// Number of recent ratios used to measure volatility.
+    pub const LOOKBACK: usize = 5;
+
+    pub fn new(min_window: usize, max_window: usize, volatility_threshold: f64) -> Self {
+        assert!(0 < min_window && min_window <= max_window);
+        assert!(volatility_threshold >= 0.0);
+        Self {
+            min_window,
+            max_window,
+            volatility_threshold,
+            state: Mutex::new(AdaptiveWindowState { round: 0, window: max_window }),
+        }
+    }
+
+    /// The window used for the most recent prediction.
+    pub fn window(&self) -> usize {
+        self.state.lock().unwrap().window
+    }
+}
+
+impl Default for AdaptiveWindowPolicy {
+    fn default() -> Self {
+        Self::new(2, 20, 0.1)
+    }
+}
+
+impl Clone for AdaptiveWindowPolicy {
+    fn clone(&self) -> Self {
+        Self {
+            min_window: self.min_window,
+            max_window: self.max_window,
+            volatility_threshold: self.volatility_threshold,
+            state: Mutex::new(self.state.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl Policy for AdaptiveWindowPolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        if history.is_empty() {
+            return random_guess(context);
+        }
+
+        let round = context.current_round(history);
+        let mut state = self.state.lock().unwrap();
+        if state.round != round {
+            state.round = round;
+            let recent = &history[history.len().saturating_sub(Self::LOOKBACK)..];
+            if recent.len() >= 2 {
+                let mean = recent.iter().sum::<f64>() / recent.len() as f64;
+                let variance =
+                    recent.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / recent.len() as f64;
+                state.window = if variance.sqrt() > self.volatility_threshold {
+                    state.window.saturating_sub(1).max(self.min_window)
+                } else {
+                    (state.window + 1).min(self.max_window)
+                };
+            }
+        }
+
+        let relevant_history = &history[history.len().saturating_sub(state.window)..];
+        relevant_history.iter().sum::<f64>() / relevant_history.len() as f64
+    }
+
+    fn name(&self) -> String {
+        "Adaptive Window".to_string()
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::AdaptiveWindow {
+            min_window: self.min_window,
+            max_window: self.max_window,
+            volatility_threshold: self.volatility_threshold,
+        }
+    }
+
+    fn copy_with_state(&self) -> Option<Arc<dyn Policy>> {
+        Some(Arc::new(self.clone()))
+    }
+}
+
+/// Bounded-rational satisficer: follows `inner` as long as its recent absolute prediction error
+/// stays within `1 - aspiration`, and falls back to the long-run mean attendance otherwise.
+///
+/// The recent error is an exponential moving average (see [`SatisficingPolicy::ERROR_SMOOTHING`])
+/// of `|inner prediction - actual ratio|`. It keeps being tracked while the fallback is in use,
+/// so the policy returns to `inner` once that predicts well again. Like [`SmoothedSelfPolicy`] every
+/// agent keeps its own state, which only advances once per round; the prediction itself always
+/// comes from the current history.
+#[derive(Debug)]
+pub struct SatisficingPolicy {
+    inner: Arc<dyn Policy>,
+    aspiration: f64,
+    state: Mutex<ErrorTracker>,
+}
+
+/// Smoothed absolute error of an inner policy's predictions, scored once per round, as tracked by
+/// [`SatisficingPolicy`] and [`ConfidenceWeightedPolicy`].
+#[derive(Debug, Clone, Default)]
+struct ErrorTracker {
+    /// Round of the latest prediction, see [`NeighborContext::current_round`]
+    round: Option<usize>,
+    /// Inner prediction for the round whose ratio is appended next
+    inner_prediction: Option<f64>,
+    recent_error: Option<f64>,
+}
+
+impl ErrorTracker {
+    /// On the first call of a round, scores the previous round's inner prediction against the
+    /// newest ratio and remembers `inner_prediction` for the next round. Returns the recent error.
+    ///
+    /// Predictions on an empty history are not scored, they are usually a random guess.
+    fn update(&mut self, history: &[f64], round: usize, inner_prediction: f64) -> Option<f64> {
+        if self.round != Some(round) {
+            self.round = Some(round);
+            if let (Some(predicted), Some(&actual)) = (self.inner_prediction, history.last()) {
+                let error = (predicted - actual).abs();
+                let smoothing = SatisficingPolicy::ERROR_SMOOTHING;
+                self.recent_error = Some(match self.recent_error {
+                    Some(recent) => smoothing * error + (1.0 - smoothing) * recent,
+                    None => error,
+                });
+            }
+            self.inner_prediction = (!history.is_empty()).then_some(inner_prediction);
+        }
+        self.recent_error
+    }
+}
+
+impl SatisficingPolicy {
+    /// Weight of the newest error in the moving average of recent errors.
+    pub const ERROR_SMOOTHING: f64 = 0.5;
+
+    pub fn new(inner: Arc<dyn Policy>, aspiration: f64) -> Self {
+        assert!((0.0..=1.0).contains(&aspiration));
+        Self {
+            inner,
+            aspiration,
+            state: Mutex::new(ErrorTracker::default()),
+        }
+    }
+
+    /// Smoothed absolute error of the inner policy, `None` until a prediction could be scored.
+    pub fn recent_error(&self) -> Option<f64> {
+        self.state.lock().unwrap().recent_error
+    }
+}
+
+impl Clone for SatisficingPolicy {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            aspiration: self.aspiration,
+            state: Mutex::new(self.state.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl Policy for SatisficingPolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        let inner_prediction = self.inner.decide_with_context(history, context);
+        let recent_error =
+            self.state.lock().unwrap().update(history, context.current_round(history), inner_prediction);
+        let satisfied = recent_error.is_none_or(|error| error <= 1.0 - self.aspiration);
+        if satisfied || history.is_empty() {
+            inner_prediction
+        } else {
+            history.iter().sum::<f64>() / history.len() as f64
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("Satisficing ({}, a={})", self.inner.name(), self.aspiration)
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::Satisficing {
+            inner: Box::new(self.inner.spec()),
+            aspiration: self.aspiration,
+        }
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.inner.is_deterministic()
+    }
+
+    fn copy_with_state(&self) -> Option<Arc<dyn Policy>> {
+        let mut copy = self.clone();
+        copy.inner = instance_for_agent(&self.inner);
+        Some(Arc::new(copy))
+    }
+}
+
+/// Hedges `inner` by its own track record: the prediction is pulled toward 0.5 (maximum
+/// uncertainty) by the share `recent_error / FULL_DISTRUST_ERROR`, so an accurate inner policy is
+/// followed fully and one that has recently been off by 0.5 or more predicts 0.5.
+///
+/// The recent error is tracked as in [`SatisficingPolicy`], and like there every agent keeps its
+/// own state, which only advances once per round.
+#[derive(Debug)]
+pub struct ConfidenceWeightedPolicy {
+    inner: Arc<dyn Policy>,
+    state: Mutex<ErrorTracker>,
+}
+
+impl ConfidenceWeightedPolicy {
+    /// Recent error at and above which `inner` is ignored.
+    pub const FULL_DISTRUST_ERROR: f64 = 0.5;
+
+    pub fn new(inner: Arc<dyn Policy>) -> Self {
+        Self {
+            inner,
+            state: Mutex::new(ErrorTracker::default()),
+        }
+    }
+
+    /// Smoothed absolute error of the inner policy, `None` until a prediction could be scored.
+    pub fn recent_error(&self) -> Option<f64> {
+        self.state.lock().unwrap().recent_error
+    }
+}
+
+impl Clone for ConfidenceWeightedPolicy {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            state: Mutex::new(self.state.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl Policy for ConfidenceWeightedPolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        let inner_prediction = self.inner.decide_with_context(history, context);
+        let recent_error =
+            self.state.lock().unwrap().update(history, context.current_round(history), inner_prediction);
+        let distrust = (recent_error.unwrap_or(0.0) / Self::FULL_DISTRUST_ERROR).min(1.0);
+        inner_prediction + distrust * (0.5 - inner_prediction)
+    }
+
+    fn name(&self) -> String {
+        format!("Confidence Weighted ({})", self.inner.name())
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::ConfidenceWeighted {
+            inner: Box::new(self.inner.spec()),
+        }
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.inner.is_deterministic()
+    }
+
+    fn copy_with_state(&self) -> Option<Arc<dyn Policy>> {
+        let mut copy = self.clone();
+        copy.inner = instance_for_agent(&self.inner);
+        Some(Arc::new(copy))
+    }
+}
+
+/// Steers toward the comfort threshold with a PID controller on the error signal
+/// `e = threshold - last_ratio`, predicting `threshold - (kp * e + ki * sum(e) + kd * delta(e))`.
+///
+/// After over-attendance the error is negative, so the prediction rises above the threshold and
+/// agents stay home. With `kp = 1` and no other gains it predicts yesterday's ratio. Before the
+/// first round it predicts the threshold itself. The name does not record the gains, so PID
+/// policies with different gains share a strategy id. Like [`SmoothedSelfPolicy`] every agent keeps
+/// its own integral and derivative, which only advance once per round.
+///
+/// Like [`TargetThresholdPolicy`] it steers toward the game's threshold unless built with
+/// [`PidPolicy::with_threshold`].
+#[derive(Debug)]
+pub struct PidPolicy {
+    threshold: Option<f64>,
+    kp: f64,
+    ki: f64,
+    kd: f64,
+    state: Mutex<PidState>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct PidState {
+    /// Round of the latest error, see [`NeighborContext::current_round`]
+    round: Option<usize>,
+    error: f64,
+    integral: f64,
+    derivative: f64,
+}
+
+impl PidPolicy {
+    pub fn new(kp: f64, ki: f64, kd: f64) -> Self {
+        assert!(kp.is_finite() && ki.is_finite() && kd.is_finite());
+        Self {
+            threshold: None,
+            kp,
+            ki,
+            kd,
+            state: Mutex::new(PidState::default()),
+        }
+    }
+
+    pub fn with_threshold(threshold: f64, kp: f64, ki: f64, kd: f64) -> Self {
+        assert!((0.0..=1.0).contains(&threshold));
+        Self { threshold: Some(threshold), ..Self::new(kp, ki, kd) }
+    }
+}
+
+impl Default for PidPolicy {
+    fn default() -> Self {
+        Self::new(0.5, 0.1, 0.1)
+    }
+}
+
+impl Clone for PidPolicy {
+    fn clone(&self) -> Self {
+        Self {
+            threshold: self.threshold,
+            kp: self.kp,
+            ki: self.ki,
+            kd: self.kd,
+            state: Mutex::new(self.state.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl Policy for PidPolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        let threshold = self.threshold.unwrap_or_else(|| context.current_threshold());
+        let Some(&last) = history.last() else {
+            return threshold;
+        };
+
+        let round = context.current_round(history);
+        let mut state = self.state.lock().unwrap();
+        if state.round != Some(round) {
+            let error = threshold - last;
+            // No derivative on the first error, there is nothing to compare it with
+            state.derivative = if state.round.is_none() { 0.0 } else { error - state.error };
+            state.integral += error;
+            state.error = error;
+            state.round = Some(round);
+        }
+
+        let control = self.kp * state.error + self.ki * state.integral + self.kd * state.derivative;
+        (threshold - control).clamp(0.0, 1.0)
+    }
+
+    fn name(&self) -> String {
+        match self.threshold {
+            Some(threshold) => format!("PID (t={})", threshold),
+            None => "PID".to_string(),
+        }
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::Pid {
+            threshold: self.threshold,
+            kp: self.kp,
+            ki: self.ki,
+            kd: self.kd,
+        }
+    }
+
+    fn copy_with_state(&self) -> Option<Arc<dyn Policy>> {
+        Some(Arc::new(self.clone()))
+    }
+}
+
+/// Trend follower extrapolating the last change: `last + k * (last - previous)`, clamped to
+/// [0, 1]. With a single ratio it predicts that ratio.
+#[derive(Debug, Clone, Copy)]
+pub struct MomentumSignPolicy {
+    k: f64,
+}
+
+impl MomentumSignPolicy {
+    pub fn new(k: f64) -> Self {
+        assert!(k.is_finite());
+        Self { k }
+    }
+}
+
+impl Policy for MomentumSignPolicy {
+    fn decide(&self, history: &[f64]) -> f64 {
+        self.decide_with_context(history, &NeighborContext::default())
+    }
+
+    fn decide_with_context(&self, history: &[f64], context: &NeighborContext) -> f64 {
+        match history {
+            [] => random_guess(context),
+            [.., previous, last] => (last + self.k * (last - previous)).clamp(0.0, 1.0),
+            [last] => *last,
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("Momentum Sign ({})", self.k)
+    }
+
+    fn spec(&self) -> PolicySpec {
+        PolicySpec::MomentumSign { k: self.k }
+    }
+}
+
+/// Replays a recorded attendance series: element `k` of the result is the policy's prediction
+/// for round `k`, made from the first `k` ratios of `history`.
+pub fn replay_policy(policy: &dyn Policy, history: &[f64]) -> Vec<f64> {
+    (0..history.len())
+        .map(|k| policy.decide(&history[..k]))
+        .collect()
+}
+
+/// A built-in policy with its parameters, as stored in saved simulation configs.
+///
+/// Unlike the name, a spec captures every parameter (e.g. the threshold of
+/// [`TargetThresholdPolicy`]) and nests the specs of wrapped policies. Random state is not
+/// captured: weights of [`WeightedHistoryPolicy`] and [`SlidingWeightedAveragePolicy`] are drawn
+/// anew by [`PolicySpec::build`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum PolicySpec {
+    AlwaysGo,
+    NeverGo,
+    Constant { value: f64 },
+    PredictFromYesterday,
+    PredictFromDayBeforeYesterday,
+    Random,
+    /// Only the window sizes supported by [`policy_from_name`] can be built
+    MovingAverage { window: usize },
+    FullHistoryAverage,
+    EvenHistoryAverage,
+    ComplexFormula,
+    Drunkard,
+    StupidNerd,
+    Uniform { low: f64, high: f64 },
+    WeightedHistory,
+    SlidingWeightedAverage,
+    ExponentialMovingAverage { alpha: f64 },
+    /// Only the window sizes supported by [`policy_from_name`] can be built
+    GeneralizedMean { window: usize, r: f64 },
+    /// `threshold` is `None` for a policy following the game's threshold
+    TargetThreshold { threshold: Option<f64>, jitter: f64 },
+    /// `threshold` is `None` for a policy following the game's threshold
+    MeanRevertingEma { threshold: Option<f64>, alpha: f64, pull: f64 },
+    SmoothedSelf { inner: Box<PolicySpec>, momentum: f64 },
+    LocalHerd { local_weight: f64 },
+    LogisticTrend { window: usize },
+    Periodic { period: usize },
+    PatternMatch { window: usize },
+    Noisy { inner: Box<PolicySpec>, sigma: f64, seed: u64 },
+    RandomSubsetAverage { k: usize, seed: u64 },
+    Committee { members: Vec<(PolicySpec, f64)> },
+    AdaptiveWindow { min_window: usize, max_window: usize, volatility_threshold: f64 },
+    Satisficing { inner: Box<PolicySpec>, aspiration: f64 },
+    /// `threshold` is `None` for a policy following the game's threshold
+    Pid { threshold: Option<f64>, kp: f64, ki: f64, kd: f64 },
+    MomentumSign { k: f64 },
+    ConfidenceWeighted { inner: Box<PolicySpec> },
+    /// A policy without a variant of its own, e.g. one defined outside this crate
+    Named(String),
+}
+
+impl PolicySpec {
+    /// Constructs the policy.
+    ///
+    /// Returns `ElFarolError::Config` if the parameters are out of range for the policy's
+    /// constructor, or for a `Named` spec that [`policy_from_name`] does not know.
+    pub fn build(&self) -> Result<Arc<dyn Policy>, ElFarolError> {
+        if !self.has_valid_params() {
+            return Err(ElFarolError::Config(format!("invalid parameters for policy {}", self.name())));
+        }
+        let unsupported = || ElFarolError::Config(format!("policy {} is not supported", self.name()));
+        Ok(match self {
+            PolicySpec::AlwaysGo => Arc::new(AlwaysGo),
+            PolicySpec::NeverGo => Arc::new(NeverGo),
+            &PolicySpec::Constant { value } => Arc::new(ConstantPolicy::new(value)),
+            PolicySpec::PredictFromYesterday => Arc::new(PredictFromYesterday),
+            PolicySpec::PredictFromDayBeforeYesterday => Arc::new(PredictFromDayBeforeYesterday),
+            PolicySpec::Random => Arc::new(RandomPolicy),
+            &PolicySpec::MovingAverage { window } => moving_average(window).ok_or_else(unsupported)?,
+            PolicySpec::FullHistoryAverage => Arc::new(FullHistoryAveragePolicy),
+            PolicySpec::EvenHistoryAverage => Arc::new(EvenHistoryAveragePolicy),
+            PolicySpec::ComplexFormula => Arc::new(ComplexFormulaPolicy),
+            PolicySpec::Drunkard => Arc::new(DrunkardPolicy),
+            PolicySpec::StupidNerd => Arc::new(StupidNerdPolicy),
+            &PolicySpec::Uniform { low, high } => Arc::new(UniformPolicy::new(low, high)),
+            PolicySpec::WeightedHistory => Arc::new(WeightedHistoryPolicy::new()),
+            PolicySpec::SlidingWeightedAverage => Arc::new(SlidingWeightedAveragePolicy::new()),
+            &PolicySpec::ExponentialMovingAverage { alpha } => Arc::new(ExponentialMovingAveragePolicy::new(alpha)),
+            &PolicySpec::GeneralizedMean { window, r } => generalized_mean(window, r).ok_or_else(unsupported)?,
+            &PolicySpec::TargetThreshold { threshold, jitter } => Arc::new(match threshold {
+                Some(threshold) => TargetThresholdPolicy::with_threshold(threshold, jitter),
+                None => TargetThresholdPolicy::new(jitter),
+            }),
+            &PolicySpec::MeanRevertingEma { threshold, alpha, pull } => Arc::new(match threshold {
+                Some(threshold) => MeanRevertingEmaPolicy::with_threshold(threshold, alpha, pull),
+                None => MeanRevertingEmaPolicy::new(alpha, pull),
+            }),
+            PolicySpec::SmoothedSelf { inner, momentum } => Arc::new(SmoothedSelfPolicy::new(inner.build()?, *momentum)),
+            &PolicySpec::LocalHerd { local_weight } => Arc::new(LocalHerdPolicy::new(local_weight)),
+            &PolicySpec::LogisticTrend { window } => Arc::new(LogisticTrendPolicy::new(window)),
+            &PolicySpec::Periodic { period } => Arc::new(PeriodicPolicy::new(period)),
+            &PolicySpec::PatternMatch { window } => Arc::new(PatternMatchPolicy::new(window)),
+            PolicySpec::Noisy { inner, sigma, seed } => Arc::new(NoisyPolicy::with_seed(inner.build()?, *sigma, *seed)),
+            &PolicySpec::RandomSubsetAverage { k, seed } => Arc::new(RandomSubsetAveragePolicy::with_seed(k, seed)),
+            PolicySpec::Committee { members } => Arc::new(CommitteePolicy::new(
+                members
+                    .iter()
+                    .map(|(member, weight)| Ok((member.build()?, *weight)))
+                    .collect::<Result<_, ElFarolError>>()?,
+            )),
+            &PolicySpec::AdaptiveWindow { min_window, max_window, volatility_threshold } => {
+                Arc::new(AdaptiveWindowPolicy::new(min_window, max_window, volatility_threshold))
+            }
+            PolicySpec::Satisficing { inner, aspiration } => {
+                Arc::new(SatisficingPolicy::new(inner.build()?, *aspiration))
+            }
+            &PolicySpec::Pid { threshold, kp, ki, kd } => Arc::new(match threshold {
+                Some(threshold) => PidPolicy::with_threshold(threshold, kp, ki, kd),
+                None => PidPolicy::new(kp, ki, kd),
+            }),
+            &PolicySpec::MomentumSign { k } => Arc::new(MomentumSignPolicy::new(k)),
+            PolicySpec::ConfidenceWeighted { inner } => Arc::new(ConfidenceWeightedPolicy::new(inner.build()?)),
+            PolicySpec::Named(name) => {
+                policy_from_name(name).ok_or_else(|| ElFarolError::Config(format!("unknown policy {}", name)))?
+            }
+        })
+    }
+
+    /// Whether the parameters of this spec (not the nested ones) pass the checks of the policy's
+    /// constructor.
+    fn has_valid_params(&self) -> bool {
+        let unit = |value: f64| (0.0..=1.0).contains(&value);
+        let threshold_ok = |threshold: Option<f64>| threshold.is_none_or(unit);
+        match *self {
+            PolicySpec::Constant { value } => unit(value),
+            PolicySpec::Uniform { low, high } => low <= high && unit(low) && unit(high),
+            PolicySpec::ExponentialMovingAverage { alpha } => alpha > 0.0 && alpha < 1.0,
+            PolicySpec::TargetThreshold { threshold, jitter } => jitter >= 0.0 && threshold_ok(threshold),
+            PolicySpec::MeanRevertingEma { threshold, alpha, pull } => {
+                alpha > 0.0 && alpha < 1.0 && unit(pull) && threshold_ok(threshold)
+            }
+            PolicySpec::SmoothedSelf { momentum, .. } => unit(momentum),
+            PolicySpec::LocalHerd { local_weight } => unit(local_weight),
+            PolicySpec::LogisticTrend { window } | PolicySpec::PatternMatch { window } => window > 0,
+            PolicySpec::Periodic { period } => period > 0,
+            PolicySpec::Noisy { sigma, .. } => sigma >= 0.0,
+            PolicySpec::RandomSubsetAverage { k, .. } => k > 0,
+            PolicySpec::Committee { ref members } => {
+                members.iter().all(|&(_, weight)| weight.is_finite() && weight >= 0.0)
+                    && members.iter().map(|(_, weight)| weight).sum::<f64>() > 0.0
+            }
+            PolicySpec::AdaptiveWindow { min_window, max_window, volatility_threshold } => {
+                0 < min_window && min_window <= max_window && volatility_threshold >= 0.0
+            }
+            PolicySpec::Satisficing { aspiration, .. } => unit(aspiration),
+            PolicySpec::Pid { threshold, kp, ki, kd } => {
+                kp.is_finite() && ki.is_finite() && kd.is_finite() && threshold_ok(threshold)
+            }
+            PolicySpec::MomentumSign { k } => k.is_finite(),
+            _ => true,
+        }
+    }
+
+    /// Name of the described policy, the same as `name()` of the built policy. Works without
+    /// building, so it also names specs that [`PolicySpec::build`] rejects.
+    pub fn name(&self) -> String {
+        match self {
+            PolicySpec::AlwaysGo => "Always Go".to_string(),
+            PolicySpec::NeverGo => "Never Go".to_string(),
+            PolicySpec::Constant { value } => format!("Constant ({})", value),
+            PolicySpec::PredictFromYesterday => "Predict from yesterday".to_string(),
+            PolicySpec::PredictFromDayBeforeYesterday => "Predict from day before yesterday".to_string(),
+            PolicySpec::Random => "Random".to_string(),
+            PolicySpec::MovingAverage { window } => format!("Moving Average ({})", window),
+            PolicySpec::FullHistoryAverage => "Full History Average".to_string(),
+            PolicySpec::EvenHistoryAverage => "Even History Average".to_string(),
+            PolicySpec::ComplexFormula => "Complex Formula".to_string(),
+            PolicySpec::Drunkard => "Drunkard".to_string(),
+            PolicySpec::StupidNerd => "Stupid Nerd".to_string(),
+            PolicySpec::Uniform { low, high } => format!("Uniform [{}..{})", low, high),
+            PolicySpec::WeightedHistory => "Weighted History".to_string(),
+            PolicySpec::SlidingWeightedAverage => "Sliding Weighted Average (5)".to_string(),
+            PolicySpec::ExponentialMovingAverage { alpha } => format!("Exponential Moving Average (a={})", alpha),
+            PolicySpec::GeneralizedMean { window, r } => format!("Generalized Mean (m={}, r={})", window, r),
+            PolicySpec::TargetThreshold { threshold: Some(threshold), jitter } => {
+                format!("Target Threshold ({}, t={})", jitter, threshold)
+            }
+            PolicySpec::TargetThreshold { threshold: None, jitter } => format!("Target Threshold ({})", jitter),
+            PolicySpec::MeanRevertingEma { threshold: Some(threshold), alpha, pull } => {
+                format!("Mean Reverting EMA (a={}, pull={}, t={})", alpha, pull, threshold)
+            }
+            PolicySpec::MeanRevertingEma { threshold: None, alpha, pull } => {
+                format!("Mean Reverting EMA (a={}, pull={})", alpha, pull)
+            }
+            PolicySpec::SmoothedSelf { inner, momentum } => format!("Smoothed Self ({}, m={})", inner.name(), momentum),
+            PolicySpec::LocalHerd { local_weight } => format!("Local Herd ({})", local_weight),
+            PolicySpec::LogisticTrend { window } => format!("Logistic Trend ({})", window),
+            PolicySpec::Periodic { period } => format!("Periodic ({})", period),
+            PolicySpec::PatternMatch { window } => format!("Pattern Match ({})", window),
+            PolicySpec::Noisy { inner, sigma, .. } => format!("Noisy({}, {})", inner.name(), sigma),
+            PolicySpec::RandomSubsetAverage { k, .. } => format!("Random Subset Avg ({})", k),
+            PolicySpec::Committee { members } => format!("Committee({})", members.len()),
+            PolicySpec::AdaptiveWindow { .. } => "Adaptive Window".to_string(),
+            PolicySpec::Satisficing { inner, aspiration } => format!("Satisficing ({}, a={})", inner.name(), aspiration),
+            PolicySpec::Pid { threshold: Some(threshold), .. } => format!("PID (t={})", threshold),
+            PolicySpec::Pid { threshold: None, .. } => "PID".to_string(),
+            PolicySpec::MomentumSign { k } => format!("Momentum Sign ({})", k),
+            PolicySpec::ConfidenceWeighted { inner } => format!("Confidence Weighted ({})", inner.name()),
+            PolicySpec::Named(name) => name.clone(),
+        }
+    }
+}
+
+/// A built-in policy that [`policy_from_name`] can construct from its `name()`.
+pub struct PolicyTemplate {
+    /// The policy name with `{}` in place of every parameter, e.g. `"Moving Average ({})"`
+    pub template: &'static str,
+    /// Human readable placeholder for every `{}` in `template`
+    pub params: &'static [&'static str],
+    /// Parameter values used for the example name shown to users
+    pub example: &'static [f64],
+    build: fn(&[f64]) -> Option<Arc<dyn Policy>>,
+}
+
+impl PolicyTemplate {
+    /// Template with the parameter placeholders filled in, e.g. `"Moving Average (N)"`.
+    pub fn syntax(&self) -> String {
+        self.params
+            .iter()
+            .fold(self.template.to_string(), |syntax, param| syntax.replacen("{}", param, 1))
+    }
+
+    /// Builds the policy for the given parameters, or `None` if they are out of range.
+    pub fn build(&self, params: &[f64]) -> Option<Arc<dyn Policy>> {
+        if params.len() != self.params.len() {
+            return None;
+        }
+        (self.build)(params)
+    }
+
+    /// Name of the policy built from the example parameters.
+    pub fn example_name(&self) -> String {
+        self.build(self.example)
+            .expect("example parameters must be valid")
+            .name()
+    }
+
+    /// Extracts the parameters if `name` follows this template.
+    fn parse(&self, name: &str) -> Option<Vec<f64>> {
+        let mut literals = self.template.split("{}");
+        let mut rest = name.strip_prefix(literals.next()?)?;
+        let mut params = Vec::new();
+        for literal in literals {
+            let end = if literal.is_empty() { rest.len() } else { rest.find(literal)? };
+            params.push(rest[..end].trim().parse().ok()?);
+            rest = &rest[end + literal.len()..];
+        }
+        rest.is_empty().then_some(params)
+    }
+}
+
+fn moving_average(window: usize) -> Option<Arc<dyn Policy>> {
+    Some(match window {
+        2 => Arc::new(MovingAveragePolicy::<2>),
+        3 => Arc::new(MovingAveragePolicy::<3>),
+        5 => Arc::new(MovingAveragePolicy::<5>),
+        10 => Arc::new(MovingAveragePolicy::<10>),
+        20 => Arc::new(MovingAveragePolicy::<20>),
+        _ => return None,
+    })
+}
+
+fn generalized_mean(window: usize, r: f64) -> Option<Arc<dyn Policy>> {
+    if r == 0.0 {
+        return None;
+    }
+    Some(match window {
+        3 => Arc::new(GeneralizedMeanPolicy::<3>::new(r)),
+        5 => Arc::new(GeneralizedMeanPolicy::<5>::new(r)),
+        10 => Arc::new(GeneralizedMeanPolicy::<10>::new(r)),
+        _ => return None,
+    })
+}
+
+/// All policies that can be built by name. Const-generic window sizes are limited to the values
+/// listed in `moving_average` and `generalized_mean`; wrapper policies are not included.
+pub static POLICY_TEMPLATES: &[PolicyTemplate] = &[
+    PolicyTemplate { template: "Always Go", params: &[], example: &[], build: |_| Some(Arc::new(AlwaysGo)) },
+    PolicyTemplate { template: "Never Go", params: &[], example: &[], build: |_| Some(Arc::new(NeverGo)) },
+    PolicyTemplate {
+        template: "Predict from yesterday",
+        params: &[],
+        example: &[],
+        build: |_| Some(Arc::new(PredictFromYesterday)),
+    },
+    PolicyTemplate {
+        template: "Predict from day before yesterday",
+        params: &[],
+        example: &[],
+        build: |_| Some(Arc::new(PredictFromDayBeforeYesterday)),
+    },
+    PolicyTemplate { template: "Random", params: &[], example: &[], build: |_| Some(Arc::new(RandomPolicy)) },
+    PolicyTemplate {
+        template: "Moving Average ({})",
+        params: &["N"],
+        example: &[5.0],
+        build: |p| moving_average(p[0] as usize),
+    },
+    PolicyTemplate {
+        template: "Full History Average",
+        params: &[],
+        example: &[],
+        build: |_| Some(Arc::new(FullHistoryAveragePolicy)),
+    },
+    PolicyTemplate {
+        template: "Even History Average",
+        params: &[],
+        example: &[],
+        build: |_| Some(Arc::new(EvenHistoryAveragePolicy)),
+    },
+    PolicyTemplate {
+        template: "Complex Formula",
+        params: &[],
+        example: &[],
+        build: |_| Some(Arc::new(ComplexFormulaPolicy)),
+    },
+    PolicyTemplate { template: "Drunkard", params: &[], example: &[], build: |_| Some(Arc::new(DrunkardPolicy)) },
+    PolicyTemplate { template: "Stupid Nerd", params: &[], example: &[], build: |_| Some(Arc::new(StupidNerdPolicy)) },
+    PolicyTemplate {
+        template: "Uniform [{}..{})",
+        params: &["LOW", "HIGH"],
+        example: &[0.25, 0.75],
+        build: |p| {
+            let valid = p[0] <= p[1] && (0.0..=1.0).contains(&p[0]) && (0.0..=1.0).contains(&p[1]);
+            valid.then(|| Arc::new(UniformPolicy::new(p[0], p[1])) as Arc<dyn Policy>)
+        },
+    },
+    PolicyTemplate {
+        template: "Weighted History",
+        params: &[],
+        example: &[],
+        build: |_| Some(Arc::new(WeightedHistoryPolicy::new())),
+    },
+    PolicyTemplate {
+        template: "Sliding Weighted Average (5)",
+        params: &[],
+        example: &[],
+        build: |_| Some(Arc::new(SlidingWeightedAveragePolicy::new())),
+    },
+    PolicyTemplate {
+        template: "Exponential Moving Average (a={})",
+        params: &["F"],
+        example: &[0.5],
+        build: |p| {
+            (p[0] > 0.0 && p[0] < 1.0)
+                .then(|| Arc::new(ExponentialMovingAveragePolicy::new(p[0])) as Arc<dyn Policy>)
+        },
+    },
+    PolicyTemplate {
+        template: "Generalized Mean (m={}, r={})",
+        params: &["M", "R"],
+        example: &[5.0, 2.0],
+        build: |p| generalized_mean(p[0] as usize, p[1]),
+    },
+    PolicyTemplate {
+        template: "Target Threshold ({})",
+        params: &["JITTER"],
+        example: &[0.05],
+        build: |p| (p[0] >= 0.0).then(|| Arc::new(TargetThresholdPolicy::new(p[0])) as Arc<dyn Policy>),
+    },
+    PolicyTemplate {
+        template: "Target Threshold ({}, t={})",
+        params: &["JITTER", "T"],
+        example: &[0.05, 0.4],
+        build: |p| {
+            (p[0] >= 0.0 && (0.0..=1.0).contains(&p[1]))
+                .then(|| Arc::new(TargetThresholdPolicy::with_threshold(p[1], p[0])) as Arc<dyn Policy>)
+        },
+    },
+    PolicyTemplate {
+        template: "Mean Reverting EMA (a={}, pull={})",
+        params: &["F", "PULL"],
+        example: &[0.5, 0.3],
+        build: |p| {
+            (p[0] > 0.0 && p[0] < 1.0 && (0.0..=1.0).contains(&p[1]))
+                .then(|| Arc::new(MeanRevertingEmaPolicy::new(p[0], p[1])) as Arc<dyn Policy>)
+        },
+    },
+    PolicyTemplate {
+        template: "Mean Reverting EMA (a={}, pull={}, t={})",
+        params: &["F", "PULL", "T"],
+        example: &[0.5, 0.3, 0.4],
+        build: |p| {
+            (p[0] > 0.0 && p[0] < 1.0 && (0.0..=1.0).contains(&p[1]) && (0.0..=1.0).contains(&p[2]))
+                .then(|| Arc::new(MeanRevertingEmaPolicy::with_threshold(p[2], p[0], p[1])) as Arc<dyn Policy>)
+        },
+    },
+    PolicyTemplate {
+        template: "Constant ({})",
+        params: &["V"],
+        example: &[0.5],
+        build: |p| (0.0..=1.0).contains(&p[0]).then(|| Arc::new(ConstantPolicy::new(p[0])) as Arc<dyn Policy>),
+    },
+    PolicyTemplate {
+        template: "Logistic Trend ({})",
+        params: &["W"],
+        example: &[5.0],
+        build: |p| {
+            (p[0] >= 1.0 && p[0].fract() == 0.0)
+                .then(|| Arc::new(LogisticTrendPolicy::new(p[0] as usize)) as Arc<dyn Policy>)
+        },
+    },
+    PolicyTemplate {
+        template: "Local Herd ({})",
+        params: &["F"],
+        example: &[0.5],
+        build: |p| {
+            (0.0..=1.0)
+                .contains(&p[0])
+                .then(|| Arc::new(LocalHerdPolicy::new(p[0])) as Arc<dyn Policy>)
+        },
+    },
+    PolicyTemplate {
+        template: "Periodic ({})",
+        params: &["P"],
+        example: &[2.0],
+        build: |p| {
+            (p[0] >= 1.0 && p[0].fract() == 0.0)
+                .then(|| Arc::new(PeriodicPolicy::new(p[0] as usize)) as Arc<dyn Policy>)
+        },
+    },
+    PolicyTemplate {
+        template: "Pattern Match ({})",
+        params: &["W"],
+        example: &[3.0],
+        build: |p| {
+            (p[0] >= 1.0 && p[0].fract() == 0.0)
+                .then(|| Arc::new(PatternMatchPolicy::new(p[0] as usize)) as Arc<dyn Policy>)
+        },
+    },
+    PolicyTemplate {
+        template: "Random Subset Avg ({})",
+        params: &["K"],
+        example: &[5.0],
+        build: |p| {
+            (p[0] >= 1.0 && p[0].fract() == 0.0)
+                .then(|| Arc::new(RandomSubsetAveragePolicy::new(p[0] as usize)) as Arc<dyn Policy>)
+        },
+    },
+    PolicyTemplate {
+        template: "Adaptive Window",
+        params: &[],
+        example: &[],
+        build: |_| Some(Arc::new(AdaptiveWindowPolicy::default())),
+    },
+    PolicyTemplate {
+        template: "PID",
+        params: &[],
+        example: &[],
+        build: |_| Some(Arc::new(PidPolicy::default())),
+    },
+    PolicyTemplate {
+        template: "PID (t={})",
+        params: &["T"],
+        example: &[0.4],
+        build: |p| {
+            (0.0..=1.0).contains(&p[0]).then(|| {
+                let default = PidPolicy::default();
+                Arc::new(PidPolicy::with_threshold(p[0], default.kp, default.ki, default.kd)) as Arc<dyn Policy>
+            })
+        },
+    },
+    PolicyTemplate {
+        template: "Momentum Sign ({})",
+        params: &["K"],
+        example: &[0.5],
+        build: |p| p[0].is_finite().then(|| Arc::new(MomentumSignPolicy::new(p[0])) as Arc<dyn Policy>),
+    },
+];
+
+/// Builds a built-in policy from its `name()`, e.g. `"Moving Average (5)"`.
+pub fn policy_from_name(name: &str) -> Option<Arc<dyn Policy>> {
+    POLICY_TEMPLATES
+        .iter()
+        .find_map(|template| template.build(&template.parse(name)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_threshold_without_jitter() {
+        let policy = TargetThresholdPolicy::new(0.0);
+        assert_eq!(policy.decide(&[]), DEFAULT_THRESHOLD);
+        assert_eq!(policy.decide(&[0.1, 0.9]), DEFAULT_THRESHOLD);
+
+        let policy = TargetThresholdPolicy::with_threshold(0.4, 0.0);
+        assert_eq!(policy.decide(&[0.5]), 0.4);
+    }
+
+    #[test]
+    fn test_target_threshold_follows_game_threshold() {
+        let context = NeighborContext { threshold: Some(0.3), ..NeighborContext::default() };
+        assert_eq!(TargetThresholdPolicy::new(0.0).decide_with_context(&[0.5], &context), 0.3);
+        let fixed = TargetThresholdPolicy::with_threshold(0.4, 0.0);
+        assert_eq!(fixed.decide_with_context(&[0.5], &context), 0.4);
+        assert_ne!(fixed.name(), TargetThresholdPolicy::new(0.0).name());
+
+        // The jitter depends only on seed, round and agent
+        let jittered = TargetThresholdPolicy::new(0.1);
+        let prediction = jittered.decide_with_context(&[0.5], &context);
+        assert!((0.2..=0.4).contains(&prediction));
+        assert_eq!(jittered.decide_with_context(&[0.5], &context), prediction);
+    }
+
+    #[test]
+    fn test_smoothed_self_full_momentum_keeps_first_prediction() {
+        let policy = SmoothedSelfPolicy::new(Arc::new(PredictFromYesterday), 1.0);
+        assert_eq!(policy.decide(&[0.2]), 0.2);
+        assert_eq!(policy.decide(&[0.2, 0.9]), 0.2);
+        assert_eq!(policy.decide(&[0.2, 0.9, 0.5]), 0.2);
+    }
+
+    #[test]
+    fn test_policy_names_round_trip() {
+        for template in POLICY_TEMPLATES {
+            let name = template.example_name();
+            let policy = policy_from_name(&name).unwrap_or_else(|| panic!("{} did not parse", name));
+            assert_eq!(policy.name(), name);
+        }
+        assert_eq!(POLICY_TEMPLATES[5].syntax(), "Moving Average (N)");
+        assert_eq!(policy_from_name("Moving Average (3)").unwrap().name(), "Moving Average (3)");
+        assert!(policy_from_name("Exponential Moving Average (a=1.5)").is_none());
+        assert!(policy_from_name("Unknown").is_none());
+    }
+
+    #[test]
+    fn test_replay_predict_from_yesterday() {
+        let history = [0.2, 0.5, 0.7, 0.4];
+        let predictions = replay_policy(&PredictFromYesterday, &history);
+        assert_eq!(predictions.len(), history.len());
+        // The first prediction is made without any history and is random
+        assert_eq!(predictions[1..], history[..3]);
+    }
+
+    #[test]
+    fn test_adaptive_window_shrinks_on_volatile_history() {
+        let policy = AdaptiveWindowPolicy::new(2, 10, 0.1);
+        assert_eq!(policy.window(), 10);
+        let volatile: Vec<f64> = (0..20).map(|i| if i % 2 == 0 { 0.1 } else { 0.9 }).collect();
+        replay_policy(&policy, &volatile);
+        assert_eq!(policy.window(), 2);
+        // The last prediction averages only the last two ratios.
+        assert!((policy.decide(&volatile) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_satisficing_follows_inner_for_every_caller() {
+        // Two calls for the same round with different histories each get their own prediction
+        let policy = SatisficingPolicy::new(Arc::new(PredictFromYesterday), 0.8);
+        assert_eq!(policy.decide(&[0.1, 0.2]), 0.2);
+        assert_eq!(policy.decide(&[0.9, 0.8]), 0.8);
+    }
+
+    #[test]
+    fn test_random_subset_average() {
+        let history = [0.1, 0.4, 0.7, 0.2];
+        let full_average = history.iter().sum::<f64>() / history.len() as f64;
+        for k in [4, 10] {
+            assert_eq!(RandomSubsetAveragePolicy::with_seed(k, 0).decide(&history), full_average);
+        }
+
+        // A sample of two is the mean of two distinct entries, depending only on the seeds, the
+        // round and the agent
+        let policy = RandomSubsetAveragePolicy::with_seed(2, 5);
+        let rebuilt = policy.spec().build().unwrap();
+        for agent in 0..10 {
+            let context = NeighborContext { agent, seed: 42, ..NeighborContext::default() };
+            let prediction = policy.decide_with_context(&history, &context);
+            assert_eq!(prediction, rebuilt.decide_with_context(&history, &context));
+            assert_eq!(prediction, policy.decide_with_context(&history, &context));
+            assert!((0.15..=0.55).contains(&prediction));
+        }
+        assert_eq!(policy.name(), "Random Subset Avg (2)");
+    }
+
+    #[test]
+    fn test_pattern_match_repeating_pattern() {
+        let policy = PatternMatchPolicy::new(3);
+        let pattern: Vec<f64> = [0.2, 0.5, 0.8].repeat(4);
+        // The current stretch matches three earlier ones exactly, all followed by 0.2
+        assert!((policy.decide(&pattern) - 0.2).abs() < 1e-12);
+        assert!((PatternMatchPolicy::new(2).decide(&pattern[..11]) - 0.8).abs() < 1e-12);
+        // Not enough history for a match yet
+        assert_eq!(policy.decide(&pattern[..3]), 0.8);
+        assert_eq!(policy.name(), "Pattern Match (3)");
+    }
+
+    #[test]
+    fn test_satisficing_keeps_inner_while_accurate() {
+        let policy = SatisficingPolicy::new(Arc::new(PredictFromYesterday), 0.8);
+        let stable = [0.5, 0.52, 0.48, 0.5, 0.51, 0.49];
+        replay_policy(&policy, &stable);
+        assert!(policy.recent_error().unwrap() <= 0.2);
+        assert_eq!(policy.decide(&stable), 0.49);
+    }
+
+    #[test]
+    fn test_satisficing_falls_back_to_mean_on_large_error() {
+        let policy = SatisficingPolicy::new(Arc::new(PredictFromYesterday), 0.8);
+        let volatile: Vec<f64> = (0..10).map(|i| if i % 2 == 0 { 0.1 } else { 0.9 }).collect();
+        replay_policy(&policy, &volatile);
+        assert!(policy.recent_error().unwrap() > 0.2);
+        assert!((policy.decide(&volatile) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_confidence_weighted_hedges_after_bad_predictions() {
+        let policy = ConfidenceWeightedPolicy::new(Arc::new(PredictFromYesterday));
+        let stable = [0.8, 0.8, 0.8, 0.8];
+        replay_policy(&policy, &stable);
+        assert_eq!(policy.decide(&stable), 0.8);
+
+        // Yesterday's ratio keeps being wrong by 0.2, so the prediction moves toward 0.5
+        let policy = ConfidenceWeightedPolicy::new(Arc::new(PredictFromYesterday));
+        let alternating = [0.8, 0.6, 0.8, 0.6, 0.8, 0.6, 0.8];
+        replay_policy(&policy, &alternating);
+        assert!((policy.recent_error().unwrap() - 0.2).abs() < 1e-12);
+        assert!((policy.decide(&alternating) - 0.68).abs() < 1e-12);
+
+        // Errors of 0.5 and more leave no trust in the inner prediction
+        let policy = ConfidenceWeightedPolicy::new(Arc::new(PredictFromYesterday));
+        let volatile: Vec<f64> = (0..10).map(|i| if i % 2 == 0 { 0.0 } else { 1.0 }).collect();
+        replay_policy(&policy, &volatile);
+        assert_eq!(policy.decide(&volatile), 0.5);
+
+        // Every caller within a round gets the hedge of its own inner prediction
+        let policy = ConfidenceWeightedPolicy::new(Arc::new(PredictFromYesterday));
+        assert_eq!(policy.decide(&[0.1, 0.2]), 0.2);
+        assert_eq!(policy.decide(&[0.9, 0.8]), 0.8);
+    }
+
+    #[test]
+    fn test_smoothed_self_advances_on_capped_history() {
+        // A history capped at two ratios keeps its length while the rounds go on
+        let policy = SmoothedSelfPolicy::new(Arc::new(PredictFromYesterday), 0.5);
+        let windows = [[0.0, 0.0], [0.0, 1.0], [1.0, 1.0]];
+        let predictions: Vec<f64> = windows
+            .iter()
+            .enumerate()
+            .map(|(i, window)| {
+                let context = NeighborContext {
+                    round: Some(i + 2),
+                    ..NeighborContext::default()
+                };
+                policy.decide_with_context(window, &context)
+            })
+            .collect();
+        assert_eq!(predictions, [0.0, 0.5, 0.75]);
+    }
+
+    #[test]
+    fn test_adaptive_window_grows_on_stable_history() {
+        let policy = AdaptiveWindowPolicy::new(2, 10, 0.1);
+        let volatile: Vec<f64> = (0..20).map(|i| if i % 2 == 0 { 0.1 } else { 0.9 }).collect();
+        replay_policy(&policy, &volatile);
+        let stable = vec![0.5; 40];
+        replay_policy(&policy, &stable);
+        assert_eq!(policy.window(), 10);
+    }
+
+    #[test]
+    fn test_periodic_predicts_phase_value() {
+        let policy = PeriodicPolicy::new(2);
+        assert_eq!(policy.decide(&[]), 0.0);
+        assert_eq!(policy.decide(&[0.8]), 0.0);
+
+        let history: Vec<f64> = (0..9).map(|i| if i % 2 == 0 { 0.2 } else { 0.8 }).collect();
+        // Next round (index 9) is in phase with the odd rounds
+        assert!((policy.decide(&history) - 0.8).abs() < 1e-12);
+        assert!((policy.decide(&history[..8]) - 0.2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_constant_ignores_history() {
+        let policy = ConstantPolicy::new(0.3);
+        assert_eq!(policy.decide(&[]), 0.3);
+        assert_eq!(policy.decide(&[0.9, 0.1, 0.5]), 0.3);
+        assert_eq!(policy.name(), "Constant (0.3)");
+        assert!(policy_from_name("Constant (1.5)").is_none());
+    }
+
+    #[test]
+    fn test_mean_reverting_ema() {
+        let history = [0.1, 0.9, 0.3];
+        let full_pull = MeanRevertingEmaPolicy::with_threshold(0.4, 0.5, 1.0);
+        assert_eq!(full_pull.decide(&history), 0.4);
+
+        let no_pull = MeanRevertingEmaPolicy::new(0.5, 0.0);
+        let ema = ExponentialMovingAveragePolicy::new(0.5).decide(&history);
+        assert!((no_pull.decide(&history) - ema).abs() < 1e-12);
+
+        let half_pull = MeanRevertingEmaPolicy::new(0.5, 0.5);
+        assert!((half_pull.decide(&history) - (ema + DEFAULT_THRESHOLD) / 2.0).abs() < 1e-12);
+
+        // Without a threshold of its own it reverts to the game's
+        let context = NeighborContext { threshold: Some(0.2), ..NeighborContext::default() };
+        assert!((half_pull.decide_with_context(&history, &context) - (ema + 0.2) / 2.0).abs() < 1e-12);
+        assert_eq!(full_pull.decide_with_context(&history, &context), 0.4);
+        assert_ne!(full_pull.name(), MeanRevertingEmaPolicy::new(0.5, 1.0).name());
+    }
+
+    #[test]
+    fn test_noisy_policy() {
+        let history = [0.2, 0.4];
+        let exact = NoisyPolicy::new(Arc::new(PredictFromYesterday), 0.0);
+        assert_eq!(exact.decide(&history), 0.4);
+        assert_eq!(exact.name(), "Noisy(Predict from yesterday, 0)");
+
+        // The noise only depends on seed, round and agent, not on earlier calls
+        let noisy = NoisyPolicy::with_seed(Arc::new(PredictFromYesterday), 0.1, 9);
+        let context = |agent| NeighborContext {
+            round: Some(7),
+            agent,
+            seed: 42,
+            ..NeighborContext::default()
+        };
+        let predictions: Vec<f64> = (0..100).map(|agent| noisy.decide_with_context(&history, &context(agent))).collect();
+        assert!(predictions.iter().all(|p| (0.0..=1.0).contains(p)));
+        assert!(predictions.iter().any(|&p| p != 0.4));
+        let mean = predictions.iter().sum::<f64>() / predictions.len() as f64;
+        assert!((mean - 0.4).abs() < 0.05);
+        let rebuilt = noisy.spec().build().unwrap();
+        assert_eq!(rebuilt.decide_with_context(&history, &context(3)), predictions[3]);
+        assert_eq!(noisy.decide_with_context(&history, &context(3)), predictions[3]);
+        let other_seed = NoisyPolicy::with_seed(Arc::new(PredictFromYesterday), 0.1, 10);
+        assert_ne!(other_seed.decide_with_context(&history, &context(3)), predictions[3]);
+    }
+
+    #[test]
+    fn test_logistic_trend_extrapolates_increasing_history() {
+        let policy = LogisticTrendPolicy::new(4);
+        let history = [0.1, 0.2, 0.3, 0.4, 0.5];
+        let prediction = policy.decide(&history);
+        assert!(prediction > 0.5 && prediction < 1.0);
+
+        // A history that is linear in log-odds is extrapolated exactly
+        let logistic = |z: f64| 1.0 / (1.0 + (-z).exp());
+        let linear: Vec<f64> = (0..5).map(|x| logistic(-1.0 + 0.5 * x as f64)).collect();
+        assert!((policy.decide(&linear) - logistic(1.5)).abs() < 1e-9);
+
+        // Saturated ratios stay strictly inside (0, 1)
+        let saturated = policy.decide(&[0.0, 0.5, 1.0, 1.0]);
+        assert!(saturated > 0.0 && saturated < 1.0);
+    }
+
+    #[test]
+    fn test_committee_policy() {
+        let committee = CommitteePolicy::new(vec![(Arc::new(AlwaysGo), 2.0), (Arc::new(NeverGo), 2.0)]);
+        assert_eq!(committee.decide(&[0.3]), 0.5);
+        assert_eq!(committee.name(), "Committee(2)");
+
+        let weighted = CommitteePolicy::new(vec![
+            (Arc::new(PredictFromYesterday), 3.0),
+            (Arc::new(NeverGo), 1.0),
+            (Arc::new(AlwaysGo), 0.0),
+        ]);
+        assert!((weighted.decide(&[0.2]) - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic(expected = "must not all be zero")]
+    fn test_committee_rejects_zero_weights() {
+        CommitteePolicy::new(vec![(Arc::new(AlwaysGo), 0.0), (Arc::new(NeverGo), 0.0)]);
+    }
+
+    #[test]
+    fn test_local_herd_follows_attending_neighbors() {
+        let policy = LocalHerdPolicy::new(0.5);
+        let history = [0.2];
+        assert_eq!(policy.decide(&history), 0.2);
+        assert_eq!(policy.decide_with_context(&history, &NeighborContext::default()), 0.2);
+
+        let all_went = NeighborContext {
+            neighbors_went: Some(1.0),
+            ..NeighborContext::default()
+        };
+        assert!((policy.decide_with_context(&history, &all_went) - 0.6).abs() < 1e-12);
+        // Policies without local information ignore the context
+        assert_eq!(PredictFromYesterday.decide_with_context(&history, &all_went), 0.2);
+    }
+
+    #[test]
+    fn test_pid_policy_counters_over_attendance() {
+        let proportional = PidPolicy::with_threshold(0.6, 0.5, 0.0, 0.0);
+        assert_eq!(proportional.decide(&[]), 0.6);
+        // Over-attendance pushes the prediction above the threshold, so agents stay home
+        assert!((proportional.decide(&[0.8]) - 0.7).abs() < 1e-12);
+        // Under-attendance pulls it below, so agents go
+        assert!((proportional.decide(&[0.8, 0.4]) - 0.5).abs() < 1e-12);
+
+        let integral = PidPolicy::with_threshold(0.6, 0.0, 1.0, 0.0);
+        integral.decide(&[0.8]);
+        // Two rounds 0.2 over the threshold accumulate
+        assert!((integral.decide(&[0.8, 0.8]) - 1.0).abs() < 1e-12);
+
+        let derivative = PidPolicy::with_threshold(0.6, 0.0, 0.0, 1.0);
+        assert_eq!(derivative.decide(&[0.8]), 0.6);
+        assert!((derivative.decide(&[0.8, 0.7]) - 0.5).abs() < 1e-12);
+        assert_eq!(policy_from_name("PID").unwrap().name(), "PID");
+
+        // Without a threshold of its own it steers toward the game's
+        let context = NeighborContext { threshold: Some(0.3), ..NeighborContext::default() };
+        assert_eq!(PidPolicy::new(0.5, 0.0, 0.0).decide_with_context(&[], &context), 0.3);
+        let proportional = PidPolicy::new(0.5, 0.0, 0.0);
+        assert!((proportional.decide_with_context(&[0.5], &context) - 0.4).abs() < 1e-12);
+        assert_eq!(PidPolicy::with_threshold(0.4, 0.5, 0.0, 0.0).name(), "PID (t=0.4)");
+    }
+
+    #[test]
+    fn test_momentum_sign_follows_the_trend() {
+        let policy = MomentumSignPolicy::new(0.5);
+        // Rising attendance is expected to keep rising, falling attendance to keep falling
+        assert!((policy.decide(&[0.4, 0.6]) - 0.7).abs() < 1e-12);
+        assert!((policy.decide(&[0.6, 0.4]) - 0.3).abs() < 1e-12);
+        assert_eq!(policy.decide(&[0.3, 0.3]), 0.3);
+        assert_eq!(policy.decide(&[0.45]), 0.45);
+        assert_eq!(MomentumSignPolicy::new(4.0).decide(&[0.5, 0.8]), 1.0);
+        assert_eq!(policy.name(), "Momentum Sign (0.5)");
+        assert_eq!(policy_from_name("Momentum Sign (2)").unwrap().name(), "Momentum Sign (2)");
+    }
+
+    #[test]
+    fn test_stochastic_policies_are_not_deterministic() {
+        let policies: Vec<Arc<dyn Policy>> = vec![
+            Arc::new(AlwaysGo),
+            Arc::new(PredictFromYesterday),
+            Arc::new(RandomPolicy),
+            Arc::new(MovingAveragePolicy::<5>),
+            Arc::new(UniformPolicy::new(0.25, 0.75)),
+            Arc::new(WeightedHistoryPolicy::new()),
+            Arc::new(SlidingWeightedAveragePolicy::new()),
+            Arc::new(TargetThresholdPolicy::with_threshold(0.4, 0.0)),
+            Arc::new(TargetThresholdPolicy::with_threshold(0.4, 0.05)),
+            Arc::new(SmoothedSelfPolicy::new(Arc::new(RandomPolicy), 0.5)),
+            Arc::new(NoisyPolicy::new(Arc::new(MovingAveragePolicy::<3>), 0.05)),
+            Arc::new(RandomSubsetAveragePolicy::new(4)),
+            Arc::new(CommitteePolicy::new(vec![(Arc::new(AlwaysGo), 1.0), (Arc::new(ConstantPolicy::new(0.5)), 2.0)])),
+            Arc::new(CommitteePolicy::new(vec![(Arc::new(AlwaysGo), 1.0), (Arc::new(RandomPolicy), 1.0)])),
+            Arc::new(SatisficingPolicy::new(Arc::new(PredictFromYesterday), 0.8)),
+        ];
+        let stochastic: Vec<String> = policies
+            .iter()
+            .filter(|policy| !policy.is_deterministic())
+            .map(|policy| policy.name())
+            .collect();
+        assert_eq!(
+            stochastic,
+            [
+                "Random",
+                "Uniform [0.25..0.75)",
+                "Weighted History",
+                "Sliding Weighted Average (5)",
+                "Smoothed Self (Random, m=0.5)",
+                "Committee(2)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_policy_specs_round_trip() {
+        let policies: Vec<Arc<dyn Policy>> = vec![
+            Arc::new(AlwaysGo),
+            Arc::new(NeverGo),
+            Arc::new(ConstantPolicy::new(0.3)),
+            Arc::new(PredictFromYesterday),
+            Arc::new(PredictFromDayBeforeYesterday),
+            Arc::new(RandomPolicy),
+            Arc::new(MovingAveragePolicy::<5>),
+            Arc::new(FullHistoryAveragePolicy),
+            Arc::new(EvenHistoryAveragePolicy),
+            Arc::new(ComplexFormulaPolicy),
+            Arc::new(DrunkardPolicy),
+            Arc::new(StupidNerdPolicy),
+            Arc::new(UniformPolicy::new(0.25, 0.75)),
+            Arc::new(WeightedHistoryPolicy::new()),
+            Arc::new(SlidingWeightedAveragePolicy::new()),
+            Arc::new(ExponentialMovingAveragePolicy::new(0.5)),
+            Arc::new(GeneralizedMeanPolicy::<3>::new(2.0)),
+            Arc::new(TargetThresholdPolicy::with_threshold(0.4, 0.05)),
+            Arc::new(MeanRevertingEmaPolicy::with_threshold(0.5, 0.3, 0.2)),
+            Arc::new(SmoothedSelfPolicy::new(Arc::new(PredictFromYesterday), 0.5)),
+            Arc::new(LocalHerdPolicy::new(0.5)),
+            Arc::new(LogisticTrendPolicy::new(4)),
+            Arc::new(PeriodicPolicy::new(2)),
+            Arc::new(PatternMatchPolicy::new(3)),
+            Arc::new(NoisyPolicy::new(Arc::new(MovingAveragePolicy::<3>), 0.05)),
+            Arc::new(RandomSubsetAveragePolicy::new(4)),
+            Arc::new(CommitteePolicy::new(vec![(Arc::new(AlwaysGo), 1.0), (Arc::new(ConstantPolicy::new(0.5)), 2.0)])),
+            Arc::new(AdaptiveWindowPolicy::new(3, 12, 0.2)),
+            Arc::new(SatisficingPolicy::new(Arc::new(PredictFromYesterday), 0.8)),
+            Arc::new(PidPolicy::with_threshold(0.6, 0.5, 0.1, 0.2)),
+            Arc::new(MomentumSignPolicy::new(0.5)),
+            Arc::new(ConfidenceWeightedPolicy::new(Arc::new(PredictFromYesterday))),
+        ];
+        for policy in policies {
+            let spec = policy.spec();
+            assert!(!matches!(spec, PolicySpec::Named(_)), "{} has no spec of its own", policy.name());
+            let decoded: PolicySpec = bincode::deserialize(&bincode::serialize(&spec).unwrap()).unwrap();
+            assert_eq!(decoded, spec);
+            assert_eq!(spec.name(), policy.name());
+            let rebuilt = decoded.build().unwrap();
+            assert_eq!(rebuilt.name(), policy.name());
+            assert_eq!(rebuilt.spec(), spec);
+        }
+
+        let named = PolicySpec::Named("Moving Average (10)".to_string());
+        assert_eq!(named.build().unwrap().name(), "Moving Average (10)");
+        assert_eq!(named.build().unwrap().spec(), PolicySpec::MovingAverage { window: 10 });
+    }
+
+    #[test]
+    fn test_policy_spec_build_reports_errors() {
+        let custom = PolicySpec::Noisy { inner: Box::new(PolicySpec::Named("Custom".to_string())), sigma: 0.1, seed: 0 };
+        assert_eq!(custom.name(), "Noisy(Custom, 0.1)");
+        assert!(matches!(custom.build(), Err(ElFarolError::Config(_))));
+
+        for invalid in [
+            PolicySpec::Constant { value: 1.5 },
+            PolicySpec::MovingAverage { window: 7 },
+            PolicySpec::Committee { members: vec![(PolicySpec::AlwaysGo, 0.0)] },
+            PolicySpec::SmoothedSelf { inner: Box::new(PolicySpec::AlwaysGo), momentum: -1.0 },
+        ] {
+            assert!(matches!(invalid.build(), Err(ElFarolError::Config(_))), "{} was built", invalid.name());
+        }
+    }
+
+    #[test]
+    fn test_deterministic_policies_repeat_their_first_guess() {
+        let context = NeighborContext { round: Some(0), agent: 7, seed: 5, ..NeighborContext::default() };
+        for template in POLICY_TEMPLATES {
+            let name = template.example_name();
+            let first = policy_from_name(&name).unwrap();
+            if first.is_deterministic() {
+                let second = policy_from_name(&name).unwrap();
+                assert_eq!(
+                    first.decide_with_context(&[], &context),
+                    second.decide_with_context(&[], &context),
+                    "{}",
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_stateful_policies_copy_their_state() {
+        // A policy whose state leaks between the instances agents get from `instance_for_agent`
+        // (e.g. one forgetting `copy_with_state`) predicts differently after another agent's
+        // instance has played, which would make parallel rounds depend on scheduling.
+        let mut policies: Vec<Arc<dyn Policy>> = POLICY_TEMPLATES
+            .iter()
+            .map(|template| template.build(template.example).unwrap())
+            .collect();
+        policies.extend([
+            Arc::new(SmoothedSelfPolicy::new(Arc::new(PredictFromYesterday), 0.5)) as Arc<dyn Policy>,
+            Arc::new(SatisficingPolicy::new(Arc::new(PidPolicy::new(0.5, 0.1, 0.1)), 0.8)),
+            Arc::new(ConfidenceWeightedPolicy::new(Arc::new(PredictFromYesterday))),
+            Arc::new(NoisyPolicy::with_seed(Arc::new(AdaptiveWindowPolicy::default()), 0.05, 1)),
+            Arc::new(CommitteePolicy::new(vec![
+                (Arc::new(PidPolicy::new(0.5, 0.1, 0.1)), 1.0),
+                (Arc::new(ConstantPolicy::new(0.5)), 1.0),
+            ])),
+        ]);
+
+        let history: Vec<f64> = (0..30).map(|i| ((i * 7) % 10) as f64 / 10.0).collect();
+        let probe = &history[..5];
+        let context = NeighborContext::default();
+        for policy in policies.iter().filter(|policy| policy.is_deterministic()) {
+            let before = instance_for_agent(policy).decide_with_context(probe, &context);
+            let other_agent = instance_for_agent(policy);
+            for round in 0..history.len() {
+                other_agent.decide_with_context(&history[..round], &context);
+            }
+            let after = instance_for_agent(policy).decide_with_context(probe, &context);
+            assert_eq!(before, after, "{} shares its state between agents", policy.name());
+        }
+    }
+}
@@ -1,58 +1,336 @@
 use super::agent::Agent;
-use super::game::Game;
-use super::policy::Policy;
-use crate::{Frame, StrategyId};
+use super::game::{Game, GameMode, DEFAULT_THRESHOLD};
+use super::metrics::grid_stability;
+use super::policy::{Policy, PolicySpec};
+use crate::{ElFarolError, Frame, StrategyId};
 use ndarray::Array2;
-use rand::Rng;
-use std::collections::HashMap;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How the grid is populated with policies at the start of a simulation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum InitPattern {
+    /// Every agent gets a uniformly random policy from `initial_strategies`
+    Random,
+    /// "Never Go" everywhere except for the four corners, which get the other strategies
+    Corners,
+    /// Columns left of the middle use strategy `left`, the others strategy `right`
+    HalfAndHalf { left: StrategyId, right: StrategyId },
+    /// Strategy ids read from a file with [`read_policy_grid`]; must be `grid_size` × `grid_size`
+    FromFile(PathBuf),
+    /// A random fraction of the agents (the given target attendance ratio) gets a strategy that
+    /// goes to the bar in the first round, the others one that stays home. Only strategies with a
+    /// fixed prediction (Always Go, Never Go and constant ones) can be classified, any other
+    /// initial strategy panics. Warm-up rounds shift the first scored round away from the target.
+    TargetAttendance(f64),
+}
+
+/// A per-iteration statistic recorded by [`Simulation`], see [`Simulation::get_statistics`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StatKind {
+    /// Number of distinct strategies on the grid, from the strategy distribution
+    UniqueStrategies,
+    /// Distance of the attendance from the comfort threshold
+    ThresholdDeviation,
+    /// Spatial correlation length of the strategy distribution
+    CorrelationLength,
+    RegretFraction,
+    DecisionChurn,
+    GridStability,
+}
+
+impl StatKind {
+    pub const ALL: [StatKind; 6] = [
+        StatKind::UniqueStrategies,
+        StatKind::ThresholdDeviation,
+        StatKind::CorrelationLength,
+        StatKind::RegretFraction,
+        StatKind::DecisionChurn,
+        StatKind::GridStability,
+    ];
+
+    /// Key of the statistic in [`Simulation::get_statistics`].
+    pub fn key(self) -> &'static str {
+        match self {
+            StatKind::UniqueStrategies => "unique_strategies",
+            StatKind::ThresholdDeviation => "threshold_deviation",
+            StatKind::CorrelationLength => "correlation_length",
+            StatKind::RegretFraction => "regret_fraction",
+            StatKind::DecisionChurn => "decision_churn",
+            StatKind::GridStability => "grid_stability",
+        }
+    }
+}
+
+/// Order in which the agents adapt their strategies after a batch of rounds.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpdateOrder {
+    /// Every agent compares itself to the grid as it was before the update, like a cellular
+    /// automaton
+    #[default]
+    Synchronous,
+    /// Agents adapt one at a time in a random order drawn from the simulation's RNG, seeing the
+    /// policies of neighbors already updated in the same sweep. Like in synchronous mode, every
+    /// neighbor is scored by its cell's performance in the last batch, including one that just
+    /// switched policy.
+    RandomSequential,
+}
+
+/// Neighborhood radius along rows and columns.
+///
+/// Equal radii use the Manhattan (or Chebyshev) distance; differing radii select the rectangle of
+/// `2 * rows + 1` by `2 * cols + 1` cells around an agent.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NeighborDistance {
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl NeighborDistance {
+    /// Same radius along rows and columns.
+    pub fn new(distance: usize) -> Self {
+        Self::rectangular(distance, distance)
+    }
+
+    pub fn rectangular(rows: usize, cols: usize) -> Self {
+        Self { rows, cols }
+    }
+}
+
+impl From<usize> for NeighborDistance {
+    fn from(distance: usize) -> Self {
+        Self::new(distance)
+    }
+}
 
 #[derive(Clone)]
 pub struct SimulationConfig {
     pub name: String,
     pub description: String,
     pub grid_size: usize,
-    pub neighbor_distance: usize,
+    pub neighbor_distance: NeighborDistance,
     pub temperature: f64,
     pub policy_retention_rate: f64,
     pub num_iterations: usize,
     pub rounds_per_update: usize,
     pub initial_strategies: Vec<Arc<dyn Policy>>,
-    pub start_random: bool,
+    pub init_pattern: InitPattern,
+    /// Attendance ratio at which the bar is considered overcrowded
+    pub threshold: f64,
+    /// Also record a frame for every game round of a batch, in addition to the one after adaptation
+    pub record_every_round: bool,
+    /// Store a histogram of the agents' predictions in every frame
+    pub record_prediction_histogram: bool,
+    /// Store the attendance of every round of the batch in the frame recorded after adaptation,
+    /// see `Frame::batch_attendance`
+    pub record_batch_attendance: bool,
+    /// Step changes of the threshold as `(iteration, threshold)`, active from that iteration on
+    pub threshold_schedule: Vec<(usize, f64)>,
+    /// Use Chebyshev (square) instead of Manhattan (diamond) distance for neighborhoods
+    pub use_chebyshev: bool,
+    /// Stop the run as soon as only a single strategy is left on the grid
+    pub stop_on_monoculture: bool,
+    /// Stop the run once `grid_stability` has been 1.0 (no agent switched policy) for this many
+    /// consecutive iterations
+    pub stop_when_frozen: Option<usize>,
+    /// Prepend the initial, pre-game grid as frame 0 when running a full simulation
+    pub record_initial_frame: bool,
+    /// Seed for the simulation's random number generator; `None` seeds from entropy
+    pub seed: Option<u64>,
+    /// Go to the bar with a logistic probability instead of the hard `prediction < threshold` rule
+    /// (or `>=` with `go_if_below` unset)
+    pub soft_decision: bool,
+    /// Scale of the logistic go probability used with `soft_decision`
+    pub softness: f64,
+    /// Go to the bar when the prediction is below the threshold ("it won't be crowded"). Unset,
+    /// agents go when they predict `>=` the threshold instead, e.g. to model agents drawn to a
+    /// busy bar. Payoffs are unchanged, and policy names assume the default direction: flipped,
+    /// "Always Go" (predicting 0.0) stays home and "Never Go" (1.0) always goes.
+    pub go_if_below: bool,
+    /// Per-round discount in (0, 1] weighting recent rounds more when comparing performances
+    pub performance_discount: f64,
+    /// Width (in cells) of a Gaussian weighting neighbors by Euclidean distance in the softmax;
+    /// `None` weights all neighbors within `neighbor_distance` equally
+    pub distance_sigma: Option<f64>,
+    /// Only keep the last N attendance ratios in the game history (bounds memory and policy cost)
+    pub max_history: Option<usize>,
+    /// Only switch to neighbors performing strictly better than the agent itself ("move uphill")
+    pub uphill_only: bool,
+    /// Spread of the fixed per-agent bias added to observed attendance ratios; each agent draws
+    /// its bias uniformly from `[-perception_noise, perception_noise]` at startup
+    pub perception_noise: f64,
+    /// Payoff used to score the agents' decisions every round
+    pub game_mode: GameMode,
+    /// Game rounds played in `Simulation::new` before the first adaptation; they build up the
+    /// attendance history without changing any policy and are not scored
+    pub warmup_rounds: usize,
+    /// Statistics computed every iteration. The ones early stopping depends on are computed
+    /// whenever it is enabled.
+    pub stats_to_collect: BTreeSet<StatKind>,
+    /// Whether agents adapt simultaneously or one after another
+    pub update_order: UpdateOrder,
+    /// Leading game rounds left out of summary metrics (see `SimulationData::settled_frames`), so
+    /// the transient after initialization does not skew them. Counted in rounds, so it means the
+    /// same with or without `record_every_round`. Frames and statistics are still recorded for
+    /// every iteration, so plots show the full series.
+    pub burn_in: usize,
 }
 
+impl SimulationConfig {
+    /// Returns the threshold in effect at `iteration`, taking `threshold_schedule` into account.
+    pub fn threshold_at(&self, iteration: usize) -> f64 {
+        self.threshold_schedule
+            .iter()
+            .filter(|(start, _)| *start <= iteration)
+            .max_by_key(|(start, _)| *start)
+            .map_or(self.threshold, |(_, threshold)| *threshold)
+    }
+
+    /// Checks that the parameters are in range, returning `ElFarolError::Config` otherwise.
+    pub fn validate(&self) -> Result<(), ElFarolError> {
+        if !(self.performance_discount > 0.0 && self.performance_discount <= 1.0) {
+            return Err(ElFarolError::Config(format!(
+                "performance_discount must be in (0, 1], got {}",
+                self.performance_discount
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Number of equally sized buckets over [0, 1] used for `Frame::prediction_histogram`.
+pub const PREDICTION_HISTOGRAM_BINS: usize = 20;
+
 impl Default for SimulationConfig {
     fn default() -> Self {
         Self {
             grid_size: 10,
-            neighbor_distance: 1,
+            neighbor_distance: NeighborDistance::new(1),
             temperature: 10.0,
             policy_retention_rate: 0.5,
             rounds_per_update: 10,
             initial_strategies: Vec::new(),
-            start_random: true,
+            init_pattern: InitPattern::Random,
             name: "Default Simulation".to_string(),
             description: "A default simulation configuration".to_string(),
             num_iterations: 100,
+            threshold: DEFAULT_THRESHOLD,
+            record_every_round: false,
+            record_prediction_histogram: false,
+            record_batch_attendance: false,
+            threshold_schedule: Vec::new(),
+            use_chebyshev: false,
+            stop_on_monoculture: false,
+            stop_when_frozen: None,
+            record_initial_frame: false,
+            seed: None,
+            soft_decision: false,
+            softness: 0.05,
+            go_if_below: true,
+            performance_discount: 1.0,
+            distance_sigma: None,
+            max_history: None,
+            uphill_only: false,
+            perception_noise: 0.0,
+            game_mode: GameMode::ElFarol,
+            warmup_rounds: 0,
+            stats_to_collect: StatKind::ALL.into_iter().collect(),
+            update_order: UpdateOrder::Synchronous,
+            burn_in: 0,
         }
     }
 }
 
+/// Running totals of the wall-clock time one phase of an iteration took, used for profiling.
+/// Kept as aggregates so long runs don't grow a buffer per iteration.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PhaseTimings {
+    /// Number of iterations measured
+    pub count: u32,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl PhaseTimings {
+    fn record(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    /// Average duration per iteration, zero before the first one.
+    pub fn mean(&self) -> Duration {
+        self.total.checked_div(self.count).unwrap_or_default()
+    }
+}
+
+/// Wall-clock durations of the two phases of the iterations run so far.
+#[derive(Debug, Clone, Default)]
+pub struct IterationTimings {
+    /// Time spent playing the game rounds of each iteration
+    pub game_rounds: PhaseTimings,
+    /// Time spent adapting strategies in each iteration
+    pub adaptation: PhaseTimings,
+}
+
 pub struct Simulation {
     game: Game,
     config: SimulationConfig,
     statistics: HashMap<String, Vec<f64>>,
-    strategy_map: HashMap<String, StrategyId>,
+    /// Policy name → strategy id. Starts with `initial_strategies` (id = index); names that show
+    /// up later (e.g. policies whose `name()` changes over time) get the next free id.
+    strategy_map: Mutex<HashMap<String, StrategyId>>,
     current_round: usize,
+    iteration: usize,
+    round_frames: Vec<Frame>,
+    rng: StdRng,
+    seed: u64,
+    timings: IterationTimings,
 }
 
 impl Simulation {
+    /// Same as [`Simulation::try_new`], but panics if the config cannot be used.
     pub fn new(config: SimulationConfig) -> Self {
-        let mut rng = rand::thread_rng();
+        Self::try_new(config).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Sets up the grid from `config.init_pattern` and plays the warm-up rounds.
+    ///
+    /// Returns `ElFarolError::Config` for out-of-range parameters and initial strategies or policy
+    /// grids that do not fit the config, and the read error if a `FromFile` grid cannot be read.
+    pub fn try_new(config: SimulationConfig) -> Result<Self, ElFarolError> {
+        // Draw a seed even when none is configured so the run can be reproduced from `seed()`.
+        // It stays below i64::MAX so it can be written as a TOML integer.
+        let seed = config
+            .seed
+            .unwrap_or_else(|| StdRng::from_entropy().gen_range(0..=i64::MAX as u64));
+        let mut rng = StdRng::seed_from_u64(seed);
         let mut grid: Array2<Agent>;
 
         if config.initial_strategies.is_empty() {
-            panic!("Initial strategies cannot be empty for random setup.");
+            return Err(ElFarolError::Config("initial strategies cannot be empty".to_string()));
+        }
+        config.validate()?;
+        if config.seed.is_some() {
+            let stochastic: Vec<String> = config
+                .initial_strategies
+                .iter()
+                .filter(|policy| !policy.is_deterministic())
+                .map(|policy| policy.name())
+                .collect();
+            if !stochastic.is_empty() {
+                log::warn!(
+                    "Seed {} is set, but these policies draw on their own randomness, so the run is not reproducible: {}",
+                    seed,
+                    stochastic.join(", ")
+                );
+            }
         }
 
         let strategy_map: HashMap<String, StrategyId> = config
@@ -62,156 +340,465 @@ impl Simulation {
             .map(|(i, policy)| (policy.name(), i as StrategyId))
             .collect();
 
-        if config.start_random {
-            // Initialize with a temporary agent for Array2::from_elem, then fill randomly
-            grid = Array2::from_elem(
-                (config.grid_size, config.grid_size),
-                Agent::new(config.initial_strategies[0].clone()),
-            ); // Placeholder
-
-            for i in 0..config.grid_size {
-                for j in 0..config.grid_size {
-                    let strategy_idx = rng.gen_range(0..config.initial_strategies.len());
-                    let strategy = config.initial_strategies[strategy_idx].clone();
-                    grid[[i, j]] = Agent::new(strategy);
+        match &config.init_pattern {
+            InitPattern::Random => {
+                // Initialize with a temporary agent for Array2::from_elem, then fill randomly
+                grid = Array2::from_elem(
+                    (config.grid_size, config.grid_size),
+                    Agent::new(config.initial_strategies[0].clone()),
+                ); // Placeholder
+
+                for i in 0..config.grid_size {
+                    for j in 0..config.grid_size {
+                        let strategy_idx = rng.gen_range(0..config.initial_strategies.len());
+                        let strategy = config.initial_strategies[strategy_idx].clone();
+                        grid[[i, j]] = Agent::new(strategy);
+                    }
                 }
             }
-        } else {
-            // Specific "Never Go with corners" setup
-            let base_policy_name = "Never Go";
-            let base_policy = config
-                .initial_strategies
-                .iter()
-                .find(|p| p.name() == base_policy_name)
-                .map(|p| p.clone())
-                .unwrap_or_else(|| {
-                    eprintln!(
-                        "Warning: Policy 'Never Go' not found in initial_strategies. Using the first available strategy as base."
-                    );
-                    if config.initial_strategies.is_empty() {
-                        panic!("Initial strategies cannot be empty for non-random setup if 'Never Go' is missing.");
-                    }
-                    config.initial_strategies[0].clone()
-                });
+            InitPattern::Corners => {
+                // Specific "Never Go with corners" setup
+                let base_policy_name = "Never Go";
+                let base_policy = config
+                    .initial_strategies
+                    .iter()
+                    .find(|p| p.name() == base_policy_name)
+                    .cloned()
+                    .unwrap_or_else(|| {
+                        eprintln!(
+                            "Warning: Policy 'Never Go' not found in initial_strategies. Using the first available strategy as base."
+                        );
+                        if config.initial_strategies.is_empty() {
+                            panic!("Initial strategies cannot be empty for non-random setup if 'Never Go' is missing.");
+                        }
+                        config.initial_strategies[0].clone()
+                    });
 
-            let other_policies: Vec<Arc<dyn Policy>> = config
-                .initial_strategies
-                .iter()
-                .filter(|p| p.name() != base_policy.name()) // Filter out the base policy by name
-                .map(|p| p.clone())
-                .collect();
+                let other_policies: Vec<Arc<dyn Policy>> = config
+                    .initial_strategies
+                    .iter()
+                    .filter(|p| p.name() != base_policy.name()) // Filter out the base policy by name
+                    .cloned()
+                    .collect();
 
-            // Initialize all cells with the base policy
-            grid = Array2::from_elem(
-                (config.grid_size, config.grid_size),
-                Agent::new(base_policy.clone()),
-            ); // Placeholder coords, actual in loop
+                // Initialize all cells with the base policy
+                grid = Array2::from_elem(
+                    (config.grid_size, config.grid_size),
+                    Agent::new(base_policy.clone()),
+                ); // Placeholder coords, actual in loop
 
-            for r in 0..config.grid_size {
-                for c in 0..config.grid_size {
-                    grid[[r, c]] = Agent::new(base_policy.clone());
+                for r in 0..config.grid_size {
+                    for c in 0..config.grid_size {
+                        grid[[r, c]] = Agent::new(base_policy.clone());
+                    }
                 }
-            }
 
-            if !other_policies.is_empty() {
-                let gs = config.grid_size;
-                if gs > 0 {
-                    // Top-left
-                    grid[[0, 0]] =
-                        Agent::new(other_policies[0 % other_policies.len()].clone());
-
-                    // Top-right
-                    if gs > 1 {
-                        grid[[0, gs - 1]] =
-                            Agent::new(other_policies[1 % other_policies.len()].clone());
-                    }
+                if !other_policies.is_empty() {
+                    let gs = config.grid_size;
+                    if gs > 0 {
+                        // Top-left
+                        grid[[0, 0]] =
+                            Agent::new(other_policies[0 % other_policies.len()].clone());
 
-                    // Bottom-left
-                    if gs > 1 {
-                        // Also implies gs > 0 already checked
-                        grid[[gs - 1, 0]] =
-                            Agent::new(other_policies[2 % other_policies.len()].clone());
-                    }
+                        // Top-right
+                        if gs > 1 {
+                            grid[[0, gs - 1]] =
+                                Agent::new(other_policies[1 % other_policies.len()].clone());
+                        }
 
-                    // Bottom-right
-                    if gs > 1 {
-                        // Also implies gs > 0 already checked
-                        grid[[gs - 1, gs - 1]] =
-                            Agent::new(other_policies[3 % other_policies.len()].clone());
+                        // Bottom-left
+                        if gs > 1 {
+                            // Also implies gs > 0 already checked
+                            grid[[gs - 1, 0]] =
+                                Agent::new(other_policies[2 % other_policies.len()].clone());
+                        }
+
+                        // Bottom-right
+                        if gs > 1 {
+                            // Also implies gs > 0 already checked
+                            grid[[gs - 1, gs - 1]] =
+                                Agent::new(other_policies[3 % other_policies.len()].clone());
+                        }
                     }
+                } else {
+                    eprintln!("Warning: No 'other' policies available for corners. All agents will start with the base policy.");
                 }
-            } else {
-                eprintln!("Warning: No 'other' policies available for corners. All agents will start with the base policy.");
+            }
+            &InitPattern::HalfAndHalf { left, right } => {
+                let num_strategies = config.initial_strategies.len();
+                if left as usize >= num_strategies || right as usize >= num_strategies {
+                    return Err(ElFarolError::Config(
+                        "HalfAndHalf policy ids must be smaller than the number of initial strategies".to_string(),
+                    ));
+                }
+                let split_column = config.grid_size / 2;
+                grid = Array2::from_shape_fn((config.grid_size, config.grid_size), |(_, c)| {
+                    let id = if c < split_column { left } else { right };
+                    Agent::new(config.initial_strategies[id as usize].clone())
+                });
+            }
+            &InitPattern::TargetAttendance(target) => {
+                if let Some(policy) = config
+                    .initial_strategies
+                    .iter()
+                    .find(|policy| fixed_prediction(policy.as_ref()).is_none())
+                {
+                    return Err(ElFarolError::Config(format!(
+                        "TargetAttendance can only place strategies with a fixed prediction (Always Go, Never Go, Constant), not {}",
+                        policy.name()
+                    )));
+                }
+                let threshold = config.threshold_at(0);
+                let (goers, stayers): (Vec<_>, Vec<_>) = config
+                    .initial_strategies
+                    .iter()
+                    .partition(|policy| {
+                        fixed_prediction(policy.as_ref())
+                            .is_some_and(|prediction| (prediction < threshold) == config.go_if_below)
+                    });
+                let num_cells = config.grid_size * config.grid_size;
+                let num_goers = (target.clamp(0.0, 1.0) * num_cells as f64).round() as usize;
+                if (num_goers > 0 && goers.is_empty()) || (num_goers < num_cells && stayers.is_empty()) {
+                    return Err(ElFarolError::Config(format!(
+                        "TargetAttendance({}) needs initial strategies that go and ones that stay home in the first round",
+                        target
+                    )));
+                }
+                let mut going = vec![false; num_cells];
+                for cell in rand::seq::index::sample(&mut rng, num_cells, num_goers).iter() {
+                    going[cell] = true;
+                }
+                grid = Array2::from_shape_fn((config.grid_size, config.grid_size), |(r, c)| {
+                    let group = if going[r * config.grid_size + c] { &goers } else { &stayers };
+                    Agent::new(Arc::clone(group[rng.gen_range(0..group.len())]))
+                });
+            }
+            InitPattern::FromFile(path) => {
+                let policy_ids = read_policy_grid(path)?;
+                if policy_ids.dim() != (config.grid_size, config.grid_size) {
+                    return Err(ElFarolError::Config(format!(
+                        "policy grid in {} is {:?}, expected {}x{}",
+                        path.display(),
+                        policy_ids.dim(),
+                        config.grid_size,
+                        config.grid_size
+                    )));
+                }
+                if let Some(&id) = policy_ids.iter().find(|&&id| id as usize >= config.initial_strategies.len()) {
+                    return Err(ElFarolError::Config(format!(
+                        "policy grid in {} uses id {}, but there are only {} initial strategies",
+                        path.display(),
+                        id,
+                        config.initial_strategies.len()
+                    )));
+                }
+                grid = policy_ids.map(|&id| Agent::new(config.initial_strategies[id as usize].clone()));
+            }
+        }
+
+        if config.perception_noise > 0.0 {
+            for agent in grid.iter_mut() {
+                agent.perception_bias =
+                    rng.gen_range(-config.perception_noise..=config.perception_noise);
             }
         }
 
-        let game = Game::new(grid);
+        let mut game = Game::new(grid, config.threshold);
+        game.set_softness(config.soft_decision.then_some(config.softness));
+        game.set_go_if_below(config.go_if_below);
+        game.set_seed(seed);
+        game.set_max_history(config.max_history);
+        game.set_mode(config.game_mode);
+        game.set_neighborhoods(Some(neighborhoods(&config)));
 
-        let sim = Self {
+        if config.warmup_rounds > 0 {
+            for _ in 0..config.warmup_rounds {
+                game.run(&mut rng);
+            }
+            // Warm-up scores must not influence the first adaptation
+            let mut grid = game.get_grid().clone();
+            grid.iter_mut().for_each(Agent::clear_performance_history);
+            game.set_grid(grid);
+        }
+
+        Ok(Self {
             game,
             config,
             statistics: HashMap::new(),
-            strategy_map,
+            strategy_map: Mutex::new(strategy_map),
             current_round: 0,
-        };
-
-        sim
+            iteration: 0,
+            round_frames: Vec::new(),
+            rng,
+            seed,
+            timings: IterationTimings::default(),
+        })
     }
 
+    /// Plays `rounds_per_update` game rounds, adapts strategies and returns the resulting frame.
+    ///
+    /// With `record_every_round` set, a frame for every round of the batch (taken before adaptation)
+    /// is buffered and can be collected with [`Simulation::take_round_frames`].
     pub fn run_iteration(&mut self) -> Frame {
-        self.game.run();
+        let threshold = self.config.threshold_at(self.iteration);
+        self.game.set_threshold(threshold);
+
+        let rounds_per_update = self.config.rounds_per_update;
+        let previous_policy_ids = self.collects(StatKind::GridStability).then(|| self.policy_id_grid());
+        let game_start = Instant::now();
+        let mut regret_sum = 0.0;
+        let mut churn_sum = 0.0;
+        let mut batch_attendance = Vec::with_capacity(rounds_per_update);
+        for round in 0..rounds_per_update {
+            self.game.run(&mut self.rng);
+            batch_attendance.extend(self.game.history.last());
+            regret_sum += self.game.regret_fraction();
+            // The first round of a batch follows an adaptation, so its flips mix in policy switches
+            if round > 0 {
+                churn_sum += self.game.decision_churn();
+            }
+            self.current_round += 1;
 
-        self.current_round += 1;
-        if self.current_round % self.config.rounds_per_update == 0 {
-            self.adapt_strategies();
+            if self.config.record_every_round {
+                let frame = self.snapshot(round);
+                self.round_frames.push(frame);
+            }
         }
-        
-        let grid = self.game.get_grid();
-        let policy_ids = grid.mapv(|agent| {
-            let name = agent.current_policy().name();
-            *self.strategy_map.get(&name).unwrap() as StrategyId
+
+        let adaptation_start = Instant::now();
+        self.adapt_strategies();
+        self.timings.game_rounds.record(adaptation_start - game_start);
+        self.timings.adaptation.record(adaptation_start.elapsed());
+        self.iteration += 1;
+
+        let mut frame = self.snapshot(rounds_per_update.saturating_sub(1));
+        frame.batch_attendance = self.config.record_batch_attendance.then_some(batch_attendance);
+        self.update_statistics(
+            &frame,
+            regret_sum / rounds_per_update.max(1) as f64,
+            churn_sum / rounds_per_update.saturating_sub(1).max(1) as f64,
+            previous_policy_ids.map(|previous| grid_stability(&previous, &frame.policy_ids)),
+        );
+        frame
+    }
+
+    /// Lazily runs the remaining iterations (up to `num_iterations`), yielding one frame each.
+    ///
+    /// The iterator also ends early once [`Simulation::should_stop`] is true. With
+    /// `record_every_round` the per-round frames are still buffered for
+    /// [`Simulation::take_round_frames`].
+    pub fn frames(&mut self) -> Frames<'_> {
+        Frames { simulation: self }
+    }
+
+    /// Returns true once an early-stopping condition enabled in the config has been reached.
+    pub fn should_stop(&self) -> bool {
+        let monoculture = self.config.stop_on_monoculture
+            && self
+                .statistics
+                .get("unique_strategies")
+                .and_then(|values| values.last())
+                .is_some_and(|&unique| unique <= 1.0);
+        let frozen = self.config.stop_when_frozen.is_some_and(|iterations| {
+            let stability = self.statistics.get("grid_stability").map_or(&[][..], Vec::as_slice);
+            let iterations = iterations.max(1);
+            stability.len() >= iterations
+                && stability[stability.len() - iterations..].iter().all(|&value| value >= 1.0)
         });
+        monoculture || frozen
+    }
+
+    /// Most common policy on the current grid and its share of all agents. Ties go to the lower id.
+    pub fn dominant_policy(&self) -> (StrategyId, f64) {
+        let policy_ids = self.policy_id_grid();
+        let counts = strategy_counts(&policy_ids);
+        let (id, count) = counts
+            .into_iter()
+            .max_by_key(|&(id, count)| (count, std::cmp::Reverse(id)))
+            .unwrap_or((0, 0));
+        (id, count as f64 / policy_ids.len().max(1) as f64)
+    }
+
+    /// Runs iterations until `unique_strategies` has stayed the same for `stable_iterations`
+    /// consecutive iterations, at most `max_iterations` of them. Returns whether it stabilized.
+    pub fn run_until_stable(&mut self, stable_iterations: usize, max_iterations: usize) -> bool {
+        let mut unchanged = 0;
+        let mut previous = None;
+        for _ in 0..max_iterations {
+            let frame = self.run_iteration();
+            let unique = Some(strategy_counts(&frame.policy_ids).len());
+            unchanged = if unique == previous { unchanged + 1 } else { 0 };
+            previous = unique;
+            if unchanged >= stable_iterations {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Switches a random `fraction` of the agents to the initial strategy `policy_id`, with a
+    /// fresh performance history. Returns the number of agents switched (some may already have
+    /// been using that policy).
+    pub fn perturb(&mut self, fraction: f64, policy_id: StrategyId, rng: &mut impl Rng) -> usize {
+        let policy = self
+            .config
+            .initial_strategies
+            .get(policy_id as usize)
+            .unwrap_or_else(|| panic!("Perturbation policy id {} is not an initial strategy.", policy_id))
+            .clone();
+        let mut grid = self.game.get_grid().clone();
+        let num_cells = ((fraction.clamp(0.0, 1.0) * grid.len() as f64).round() as usize).min(grid.len());
+        let cells = rand::seq::index::sample(rng, grid.len(), num_cells);
+        let cols = grid.ncols();
+        for cell in cells.iter() {
+            let agent = &mut grid[[cell / cols, cell % cols]];
+            let perception_bias = agent.perception_bias;
+            *agent = Agent::new(policy.clone());
+            agent.perception_bias = perception_bias;
+        }
+        self.game.set_grid(grid);
+        num_cells
+    }
+
+    /// Records the per-iteration statistics; `regret_fraction` is averaged over the iteration's
+    /// rounds, `decision_churn` over all but its first round, and `grid_stability` compares the
+    /// policies before and after the iteration (`None` if it is not collected).
+    fn update_statistics(
+        &mut self,
+        frame: &Frame,
+        regret_fraction: f64,
+        decision_churn: f64,
+        grid_stability: Option<f64>,
+    ) {
+        for kind in StatKind::ALL {
+            if !self.collects(kind) {
+                continue;
+            }
+            let value = match kind {
+                StatKind::UniqueStrategies => Some(strategy_counts(&frame.policy_ids).len() as f64),
+                // Lower means better coordination
+                StatKind::ThresholdDeviation => Some((frame.attendance_ratio - frame.threshold).abs()),
+                StatKind::CorrelationLength => Some(correlation_length(&frame.policy_ids)),
+                StatKind::RegretFraction => Some(regret_fraction),
+                StatKind::DecisionChurn => Some(decision_churn),
+                StatKind::GridStability => grid_stability,
+            };
+            if let Some(value) = value {
+                self.statistics.entry(kind.key().to_string()).or_default().push(value);
+            }
+        }
+    }
+
+    /// Whether `kind` is recorded, either because it was requested or because an enabled
+    /// early-stopping condition needs it.
+    fn collects(&self, kind: StatKind) -> bool {
+        self.config.stats_to_collect.contains(&kind)
+            || match kind {
+                StatKind::UniqueStrategies => self.config.stop_on_monoculture,
+                StatKind::GridStability => self.config.stop_when_frozen.is_some(),
+                _ => false,
+            }
+    }
+
+    /// Seed of the simulation's random number generator (drawn from entropy if not configured).
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Number of iterations run so far.
+    pub fn iteration(&self) -> usize {
+        self.iteration
+    }
+
+    pub fn timings(&self) -> &IterationTimings {
+        &self.timings
+    }
+
+    /// Snapshot of the grid before the first iteration. Predictions are all zero unless
+    /// `warmup_rounds` were played.
+    pub fn initial_frame(&self) -> Frame {
+        self.snapshot(0)
+    }
+
+    /// Returns the per-round frames recorded since the last call, oldest first.
+    pub fn take_round_frames(&mut self) -> Vec<Frame> {
+        std::mem::take(&mut self.round_frames)
+    }
+
+    /// Returns the strategy id of every agent on the current grid.
+    ///
+    /// Agents with stateless policies share one instance (see
+    /// [`instance_for_agent`](super::policy::instance_for_agent)), so names are built once per
+    /// distinct instance rather than once per cell. Stateful policies are copied per agent and
+    /// named per cell.
+    pub fn policy_id_grid(&self) -> Array2<StrategyId> {
+        let mut strategy_map = self.strategy_map.lock().unwrap();
+        let mut ids_by_instance: HashMap<*const (), StrategyId> = HashMap::new();
+        self.game.get_grid().map(|agent| {
+            let instance = Arc::as_ptr(agent.policy()) as *const ();
+            *ids_by_instance
+                .entry(instance)
+                .or_insert_with(|| strategy_id(&mut strategy_map, agent.policy().name()))
+        })
+    }
+
+    /// Policy names indexed by strategy id: the initial strategies followed by any names first
+    /// seen during the run.
+    pub fn strategy_names(&self) -> Vec<String> {
+        let strategy_map = self.strategy_map.lock().unwrap();
+        // Duplicate initial names leave gaps, so size by the largest id rather than the map length
+        let num_ids = strategy_map.values().max().map_or(0, |&id| id as usize + 1);
+        let mut names = vec![String::new(); num_ids];
+        for (name, &id) in strategy_map.iter() {
+            names[id as usize] = name.clone();
+        }
+        names
+    }
+
+    fn snapshot(&self, round: usize) -> Frame {
+        let grid = self.game.get_grid();
+        let policy_ids = self.policy_id_grid();
         let predictions = grid.mapv(|agent| agent.last_prediction.unwrap_or(0.0));
         let attendance_ratio = *self.game.history.last().unwrap_or(&0.0);
-
+        let prediction_histogram = self
+            .config
+            .record_prediction_histogram
+            .then(|| prediction_histogram(&predictions, PREDICTION_HISTOGRAM_BINS));
 
         Frame {
             policy_ids,
             predictions,
             attendance_ratio,
+            round,
+            prediction_histogram,
+            batch_attendance: None,
+            threshold: self.game.threshold(),
         }
     }
 
     fn adapt_strategies(&mut self) {
         let grid = self.game.get_grid();
         let mut new_grid = grid.clone();
-        let temperature = self.config.temperature;
-        let policy_retention_rate = self.config.policy_retention_rate;
+        let size = self.config.grid_size;
+        let performances = grid.map(|agent| agent.discounted_performance(self.config.performance_discount));
 
-        for i in 0..self.config.grid_size {
-            for j in 0..self.config.grid_size {
-                let mut neighbors = Vec::new();
-
-                // Find neighbors within Manhattan distance
-                let neighbor_distance = self.config.neighbor_distance as isize;
-                for ni in (i as isize - neighbor_distance).max(0)
-                    ..=(i as isize + neighbor_distance).min(self.config.grid_size as isize - 1)
-                {
-                    for nj in (j as isize - neighbor_distance).max(0)
-                        ..=(j as isize + neighbor_distance).min(self.config.grid_size as isize - 1)
-                    {
-                        let distance = (i as isize - ni).abs() + (j as isize - nj).abs();
-                        if distance <= neighbor_distance {
-                            neighbors.push((
-                                &grid[[ni as usize, nj as usize]],
-                                grid[[ni as usize, nj as usize]].performance(),
-                            ));
-                        }
+        match self.config.update_order {
+            UpdateOrder::Synchronous => {
+                for i in 0..size {
+                    for j in 0..size {
+                        adapt_agent(&self.config, grid, &performances, &mut new_grid[[i, j]], [i, j], &mut self.rng);
                     }
                 }
-
-                // Adapt strategy - agent.performance() will use accumulated history
-                new_grid[[i, j]].adapt_strategy(&neighbors, temperature, policy_retention_rate);
+            }
+            UpdateOrder::RandomSequential => {
+                let mut order: Vec<[usize; 2]> = (0..size).flat_map(|i| (0..size).map(move |j| [i, j])).collect();
+                order.shuffle(&mut self.rng);
+                for position in order {
+                    // Adapt a copy so the neighbors can be read from the grid being updated
+                    let mut agent = new_grid[position].clone();
+                    adapt_agent(&self.config, &new_grid, &performances, &mut agent, position, &mut self.rng);
+                    new_grid[position] = agent;
+                }
             }
         }
 
@@ -231,10 +818,317 @@ impl Simulation {
     }
 }
 
+/// Iterator over the frames of a simulation, see [`Simulation::frames`].
+pub struct Frames<'a> {
+    simulation: &'a mut Simulation,
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        let simulation = &mut *self.simulation;
+        if simulation.iteration >= simulation.config.num_iterations || simulation.should_stop() {
+            return None;
+        }
+        Some(simulation.run_iteration())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.simulation.config.num_iterations.saturating_sub(self.simulation.iteration);
+        (0, Some(remaining))
+    }
+}
+
+// Once exhausted, the iteration count or the stopping condition never changes back
+impl std::iter::FusedIterator for Frames<'_> {}
+
+/// Parameters of [`run_perturbation_experiment`].
+#[derive(Debug, Clone)]
+pub struct PerturbationExperiment {
+    /// Iterations `unique_strategies` must stay unchanged to count as an equilibrium
+    pub stable_iterations: usize,
+    /// Upper bound on the iterations spent reaching the equilibrium
+    pub max_iterations: usize,
+    /// Fraction of agents switched to `policy_id`
+    pub fraction: f64,
+    /// Initial strategy the perturbed agents switch to
+    pub policy_id: StrategyId,
+    /// Iterations run after the perturbation
+    pub recovery_iterations: usize,
+    /// Largest difference in any strategy's share still counted as the same state
+    pub tolerance: f64,
+}
+
+impl Default for PerturbationExperiment {
+    fn default() -> Self {
+        Self {
+            stable_iterations: 10,
+            max_iterations: 1000,
+            fraction: 0.1,
+            policy_id: 0,
+            recovery_iterations: 100,
+            tolerance: 0.05,
+        }
+    }
+}
+
+/// Outcome of [`run_perturbation_experiment`].
+#[derive(Debug, Clone)]
+pub struct PerturbationOutcome {
+    /// Whether `unique_strategies` stabilized before the perturbation
+    pub reached_equilibrium: bool,
+    /// Strategy ids right before the perturbation
+    pub before: Array2<StrategyId>,
+    /// Strategy ids after the recovery iterations
+    pub after: Array2<StrategyId>,
+    /// Whether every strategy's share after recovery is within `tolerance` of its share before
+    pub returned: bool,
+}
+
+/// Runs a simulation to equilibrium, perturbs it with [`Simulation::perturb`] and checks whether
+/// the strategy shares return to where they were.
+///
+/// The perturbation draws from its own generator seeded with the simulation's seed, so the whole
+/// experiment is reproducible from `config.seed`.
+pub fn run_perturbation_experiment(
+    config: SimulationConfig,
+    experiment: &PerturbationExperiment,
+) -> PerturbationOutcome {
+    let mut simulation = Simulation::new(config);
+    let reached_equilibrium =
+        simulation.run_until_stable(experiment.stable_iterations, experiment.max_iterations);
+    let before = simulation.policy_id_grid();
+
+    let mut rng = StdRng::seed_from_u64(simulation.seed());
+    simulation.perturb(experiment.fraction, experiment.policy_id, &mut rng);
+    for _ in 0..experiment.recovery_iterations {
+        simulation.run_iteration();
+    }
+    let after = simulation.policy_id_grid();
+
+    let shares_before = strategy_counts(&before);
+    let shares_after = strategy_counts(&after);
+    let total = before.len().max(1) as f64;
+    let returned = shares_before
+        .keys()
+        .chain(shares_after.keys())
+        .all(|id| {
+            let share = |counts: &HashMap<StrategyId, usize>| counts.get(id).copied().unwrap_or(0) as f64 / total;
+            (share(&shares_before) - share(&shares_after)).abs() <= experiment.tolerance
+        });
+
+    PerturbationOutcome {
+        reached_equilibrium,
+        before,
+        after,
+        returned,
+    }
+}
+
+/// Returns the grid positions within `neighbor_distance` of `(i, j)`, including the cell itself.
+fn neighbor_positions(config: &SimulationConfig, i: usize, j: usize) -> Vec<[usize; 2]> {
+    let mut positions = Vec::new();
+    let NeighborDistance { rows, cols } = config.neighbor_distance;
+    let (row_radius, col_radius) = (rows as isize, cols as isize);
+    for ni in (i as isize - row_radius).max(0)..=(i as isize + row_radius).min(config.grid_size as isize - 1) {
+        for nj in (j as isize - col_radius).max(0)..=(j as isize + col_radius).min(config.grid_size as isize - 1) {
+            let (di, dj) = ((i as isize - ni).abs(), (j as isize - nj).abs());
+            let is_neighbor = if rows != cols {
+                // The ranges above already bound the rectangle
+                true
+            } else if config.use_chebyshev {
+                di.max(dj) <= row_radius
+            } else {
+                di + dj <= row_radius
+            };
+            if is_neighbor {
+                positions.push([ni as usize, nj as usize]);
+            }
+        }
+    }
+    positions
+}
+
+/// Flat row-major indices of every cell's neighbors, excluding the cell itself, for
+/// [`Game::set_neighborhoods`].
+fn neighborhoods(config: &SimulationConfig) -> Vec<Vec<usize>> {
+    let size = config.grid_size;
+    (0..size * size)
+        .map(|index| {
+            let (i, j) = (index / size, index % size);
+            neighbor_positions(config, i, j)
+                .into_iter()
+                .filter(|&position| position != [i, j])
+                .map(|[ni, nj]| ni * size + nj)
+                .collect()
+        })
+        .collect()
+}
+
+/// Prediction of a strategy that predicts the same on every history, `None` for any other.
+fn fixed_prediction(policy: &dyn Policy) -> Option<f64> {
+    match policy.spec() {
+        spec @ (PolicySpec::AlwaysGo | PolicySpec::NeverGo | PolicySpec::Constant { .. }) => {
+            spec.build().ok().map(|policy| policy.decide(&[]))
+        }
+        _ => None,
+    }
+}
+
+/// Id of the policy called `name`, assigning the next free id to names not seen before.
+fn strategy_id(strategy_map: &mut HashMap<String, StrategyId>, name: String) -> StrategyId {
+    if let Some(&id) = strategy_map.get(&name) {
+        return id;
+    }
+    let next_id = strategy_map.values().max().map_or(0, |&id| id as usize + 1);
+    let id = StrategyId::try_from(next_id)
+        .unwrap_or_else(|_| panic!("Too many distinct policy names, cannot assign an id to {}", name));
+    strategy_map.insert(name, id);
+    id
+}
+
+/// Reads a grid of strategy ids, one row per line.
+///
+/// `.png` files are read as grayscale images whose pixel values are the ids (row = image row);
+/// any other file is parsed as comma-separated integers.
+pub fn read_policy_grid(path: &Path) -> Result<Array2<StrategyId>, ElFarolError> {
+    let is_png = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+    if is_png {
+        let image = image::open(path)?.to_luma8();
+        let (width, height) = image.dimensions();
+        return Ok(Array2::from_shape_fn((height as usize, width as usize), |(r, c)| {
+            image.get_pixel(c as u32, r as u32)[0]
+        }));
+    }
+
+    let rows: Vec<Vec<StrategyId>> = std::fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split(',')
+                .map(|id| id.trim().parse::<StrategyId>())
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<_, _>>()
+        .map_err(|e| ElFarolError::Decode(Box::new(e)))?;
+    let num_cols = rows.first().map_or(0, Vec::len);
+    if rows.iter().any(|row| row.len() != num_cols) {
+        return Err(ElFarolError::Decode("all rows of the policy grid must have the same length".into()));
+    }
+    Array2::from_shape_vec((rows.len(), num_cols), rows.concat()).map_err(|e| ElFarolError::Decode(Box::new(e)))
+}
+
+/// Number of agents per strategy id present on the grid.
+fn strategy_counts(policy_ids: &Array2<StrategyId>) -> HashMap<StrategyId, usize> {
+    let mut counts = HashMap::new();
+    for &id in policy_ids.iter() {
+        *counts.entry(id).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Distance (in cells) at which the spatial autocorrelation of policy ids drops below 1/e.
+///
+/// The correlation at distance `d` compares the probability that two cells `d` apart along a row
+/// or column share a policy with the probability `Σ p²` expected for a shuffled grid:
+/// `C(d) = (P_same(d) - Σ p²) / (1 - Σ p²)`, with `C(0) = 1`. The crossing is interpolated
+/// linearly between integer distances. Distances are measured without wrapping around the grid
+/// edges (the grid is not toroidal), up to half the grid size, which is returned if the
+/// correlation never drops below 1/e (e.g. for a single-policy grid).
+pub fn correlation_length(policy_ids: &Array2<StrategyId>) -> f64 {
+    let (rows, cols) = policy_ids.dim();
+    let max_distance = rows.max(cols) / 2;
+    let total = policy_ids.len() as f64;
+    let random_same: f64 = strategy_counts(policy_ids)
+        .values()
+        .map(|&count| (count as f64 / total).powi(2))
+        .sum();
+    if random_same >= 1.0 - 1e-12 {
+        return max_distance as f64;
+    }
+
+    let threshold = (-1.0f64).exp();
+    let mut previous = 1.0;
+    for d in 1..=max_distance {
+        let (mut same, mut pairs) = (0usize, 0usize);
+        for ((i, j), id) in policy_ids.indexed_iter() {
+            if i + d < rows {
+                same += (policy_ids[[i + d, j]] == *id) as usize;
+                pairs += 1;
+            }
+            if j + d < cols {
+                same += (policy_ids[[i, j + d]] == *id) as usize;
+                pairs += 1;
+            }
+        }
+        let correlation = (same as f64 / pairs as f64 - random_same) / (1.0 - random_same);
+        if correlation < threshold {
+            return (d - 1) as f64 + (previous - threshold) / (previous - correlation);
+        }
+        previous = correlation;
+    }
+    max_distance as f64
+}
+
+/// Gaussian weight `exp(-d² / (2σ²))` of a neighbor at offset `(di, dj)`, with `d` the Euclidean distance.
+pub fn gaussian_distance_weight(di: usize, dj: usize, sigma: f64) -> f64 {
+    let squared_distance = (di * di + dj * dj) as f64;
+    (-squared_distance / (2.0 * sigma * sigma)).exp()
+}
+
+/// Lets `agent`, sitting at `[i, j]`, adapt its strategy to its neighbors in `grid`, scoring every
+/// cell by its discounted performance in `performances`.
+fn adapt_agent(
+    config: &SimulationConfig,
+    grid: &Array2<Agent>,
+    performances: &Array2<f64>,
+    agent: &mut Agent,
+    [i, j]: [usize; 2],
+    rng: &mut StdRng,
+) {
+    let positions = neighbor_positions(config, i, j);
+    let neighbors: Vec<(&Agent, f64)> = positions.iter().map(|&pos| (&grid[pos], performances[pos])).collect();
+    let distance_weights: Option<Vec<f64>> = config.distance_sigma.map(|sigma| {
+        positions
+            .iter()
+            .map(|&[ni, nj]| gaussian_distance_weight(ni.abs_diff(i), nj.abs_diff(j), sigma))
+            .collect()
+    });
+
+    let uphill_from = config.uphill_only.then_some(performances[[i, j]]);
+
+    // Adapt strategy - agent.performance() will use accumulated history
+    agent.adapt_strategy(
+        &neighbors,
+        distance_weights.as_deref(),
+        uphill_from,
+        config.temperature,
+        config.policy_retention_rate,
+        rng,
+    );
+}
+
+/// Counts predictions into `bins` equally sized buckets over [0, 1].
+fn prediction_histogram(predictions: &Array2<f64>, bins: usize) -> Vec<u32> {
+    let mut histogram = vec![0u32; bins];
+    if bins == 0 {
+        return histogram;
+    }
+    for &prediction in predictions.iter() {
+        let bin = ((prediction.clamp(0.0, 1.0) * bins as f64) as usize).min(bins - 1);
+        histogram[bin] += 1;
+    }
+    histogram
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::super::policy::{AlwaysGo, NeverGo};
+    use super::super::agent::weighted_softmax_selection;
+    use super::super::policy::{AlwaysGo, ConstantPolicy, NeverGo, PredictFromYesterday, RandomPolicy, TargetThresholdPolicy};
     use std::sync::Arc;
 
     #[test]
@@ -243,15 +1137,625 @@ mod tests {
             name: "test".to_string(),
             description: "test description".to_string(),
             grid_size: 2,
-            neighbor_distance: 1,
+            neighbor_distance: NeighborDistance::new(1),
             temperature: 1.0,
             policy_retention_rate: 0.5,
             num_iterations: 10,
             rounds_per_update: 10,
             initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
-            start_random: true,
+            init_pattern: InitPattern::Random,
+            threshold: 0.6,
+            ..Default::default()
         };
         let sim = Simulation::new(config);
         assert_eq!(sim.config.grid_size, 2);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_record_every_round() {
+        let config = SimulationConfig {
+            grid_size: 3,
+            num_iterations: 4,
+            rounds_per_update: 3,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            record_every_round: true,
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config.clone());
+
+        let mut frames = Vec::new();
+        for _ in 0..config.num_iterations {
+            let frame = sim.run_iteration();
+            frames.extend(sim.take_round_frames());
+            frames.push(frame);
+        }
+
+        assert_eq!(frames.len(), config.num_iterations * (config.rounds_per_update + 1));
+        let rounds: Vec<usize> = frames.iter().take(4).map(|f| f.round).collect();
+        assert_eq!(rounds, vec![0, 1, 2, 2]);
+        // The last round keeps its own frame, recorded before the policies switched
+        assert_eq!(frames[2].attendance_ratio, frames[3].attendance_ratio);
+        assert_eq!(frames[2].policy_ids, frames[1].policy_ids);
+    }
+
+    #[test]
+    fn test_prediction_histogram_counts_all_agents() {
+        let config = SimulationConfig {
+            grid_size: 5,
+            rounds_per_update: 2,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            record_prediction_histogram: true,
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        let frame = sim.run_iteration();
+
+        let histogram = frame.prediction_histogram.unwrap();
+        assert_eq!(histogram.len(), PREDICTION_HISTOGRAM_BINS);
+        assert_eq!(histogram.iter().sum::<u32>(), 25);
+    }
+
+    #[test]
+    fn test_threshold_schedule() {
+        let config = SimulationConfig {
+            grid_size: 3,
+            rounds_per_update: 1,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            threshold: 0.6,
+            threshold_schedule: vec![(2, 0.3)],
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+
+        let thresholds: Vec<f64> = (0..4).map(|_| sim.run_iteration().threshold).collect();
+        assert_eq!(thresholds, vec![0.6, 0.6, 0.3, 0.3]);
+    }
+
+    #[test]
+    fn test_chebyshev_neighborhood() {
+        let mut config = SimulationConfig {
+            grid_size: 7,
+            neighbor_distance: NeighborDistance::new(2),
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            ..Default::default()
+        };
+        let manhattan = Simulation::new(config.clone());
+        config.use_chebyshev = true;
+        let chebyshev = Simulation::new(config);
+
+        assert_eq!(neighbor_positions(&manhattan.config, 3, 3).len(), 13);
+        assert_eq!(neighbor_positions(&chebyshev.config, 3, 3).len(), 25);
+    }
+
+    #[test]
+    fn test_unique_strategies_monoculture() {
+        let config = SimulationConfig {
+            grid_size: 4,
+            rounds_per_update: 1,
+            initial_strategies: vec![Arc::new(AlwaysGo)],
+            stop_on_monoculture: true,
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        assert!(!sim.should_stop());
+
+        sim.run_iteration();
+        assert_eq!(sim.get_statistics()["unique_strategies"], vec![1.0]);
+        assert!(sim.should_stop());
+    }
+
+    #[test]
+    fn test_initial_frame_matches_setup() {
+        let config = SimulationConfig {
+            grid_size: 4,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            init_pattern: InitPattern::Corners,
+            ..Default::default()
+        };
+        let sim = Simulation::new(config);
+        let frame = sim.initial_frame();
+
+        // "Never Go" everywhere except for the four corners
+        let always_go_cells: Vec<(usize, usize)> = frame
+            .policy_ids
+            .indexed_iter()
+            .filter(|(_, &id)| id == 0)
+            .map(|(pos, _)| pos)
+            .collect();
+        assert_eq!(always_go_cells, vec![(0, 0), (0, 3), (3, 0), (3, 3)]);
+        assert!(frame.predictions.iter().all(|&p| p == 0.0));
+        assert_eq!(frame.attendance_ratio, 0.0);
+    }
+
+    #[test]
+    fn test_policy_id_grid_matches_frame() {
+        let config = SimulationConfig {
+            grid_size: 5,
+            rounds_per_update: 2,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        let frame = sim.run_iteration();
+        assert_eq!(sim.policy_id_grid(), frame.policy_ids);
+    }
+
+    #[test]
+    fn test_zero_neighbor_distance_freezes_layout() {
+        let config = SimulationConfig {
+            grid_size: 6,
+            neighbor_distance: NeighborDistance::new(0),
+            policy_retention_rate: 0.0,
+            rounds_per_update: 2,
+            initial_strategies: vec![
+                Arc::new(AlwaysGo),
+                Arc::new(NeverGo),
+                Arc::new(PredictFromYesterday),
+                Arc::new(RandomPolicy),
+            ],
+            seed: Some(7),
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        assert_eq!(neighbor_positions(&sim.config, 2, 3), vec![[2, 3]]);
+
+        let initial = sim.policy_id_grid();
+        for _ in 0..5 {
+            assert_eq!(sim.run_iteration().policy_ids, initial);
+        }
+    }
+
+    #[test]
+    fn test_diagonal_neighbor_weighs_less() {
+        let orthogonal = gaussian_distance_weight(1, 0, 1.0);
+        let diagonal = gaussian_distance_weight(1, 1, 1.0);
+        assert_eq!(gaussian_distance_weight(0, 0, 1.0), 1.0);
+        assert!(diagonal < orthogonal);
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let weights = [orthogonal, diagonal];
+        let orthogonal_picks = (0..2_000)
+            .filter(|_| weighted_softmax_selection(&[1.0, 1.0], Some(&weights), 1.0, &mut rng) == 0)
+            .count();
+        assert!(orthogonal_picks > 1_100);
+    }
+
+    #[test]
+    fn test_timings_recorded_per_iteration() {
+        let config = SimulationConfig {
+            grid_size: 3,
+            rounds_per_update: 2,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        for _ in 0..3 {
+            sim.run_iteration();
+        }
+        let timings = sim.timings();
+        assert_eq!(timings.game_rounds.count, 3);
+        assert_eq!(timings.adaptation.count, 3);
+        assert!(timings.game_rounds.max <= timings.game_rounds.total);
+        assert!(timings.game_rounds.mean() <= timings.game_rounds.max);
+    }
+
+    #[test]
+    fn test_rectangular_neighborhood() {
+        let config = SimulationConfig {
+            grid_size: 5,
+            neighbor_distance: NeighborDistance::rectangular(0, 1),
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            ..Default::default()
+        };
+        let sim = Simulation::new(config);
+        assert_eq!(neighbor_positions(&sim.config, 2, 2), vec![[2, 1], [2, 2], [2, 3]]);
+        assert_eq!(neighbor_positions(&sim.config, 0, 4), vec![[0, 3], [0, 4]]);
+    }
+
+    #[test]
+    fn test_dominant_policy() {
+        let config = SimulationConfig {
+            grid_size: 4,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            init_pattern: InitPattern::Corners,
+            ..Default::default()
+        };
+        let sim = Simulation::new(config);
+        // "Never Go" everywhere except for the four corners
+        assert_eq!(sim.dominant_policy(), (1, 0.75));
+    }
+
+    #[test]
+    fn test_half_and_half_initialization() {
+        let config = SimulationConfig {
+            grid_size: 5,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            init_pattern: InitPattern::HalfAndHalf { left: 1, right: 0 },
+            ..Default::default()
+        };
+        let sim = Simulation::new(config);
+        let policy_ids = sim.policy_id_grid();
+        for row in policy_ids.rows() {
+            assert_eq!(row.to_vec(), vec![1, 1, 0, 0, 0]);
+        }
+    }
+
+    #[test]
+    fn test_threshold_deviation_is_zero_at_threshold() {
+        // Half the grid always goes and nobody ever switches, so attendance stays at 0.5
+        let config = SimulationConfig {
+            grid_size: 4,
+            num_iterations: 3,
+            rounds_per_update: 2,
+            threshold: 0.5,
+            policy_retention_rate: 1.0,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            init_pattern: InitPattern::HalfAndHalf { left: 0, right: 1 },
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        for _ in 0..3 {
+            sim.run_iteration();
+        }
+        assert_eq!(sim.get_statistics()["threshold_deviation"], vec![0.0; 3]);
+    }
+
+    #[test]
+    fn test_perception_noise_draws_bounded_biases() {
+        let config = SimulationConfig {
+            grid_size: 5,
+            initial_strategies: vec![Arc::new(PredictFromYesterday)],
+            seed: Some(1),
+            ..Default::default()
+        };
+        let sim = Simulation::new(config.clone());
+        assert!(sim.game.get_grid().iter().all(|agent| agent.perception_bias == 0.0));
+
+        let sim = Simulation::new(SimulationConfig { perception_noise: 0.05, ..config });
+        let biases: Vec<f64> = sim.game.get_grid().iter().map(|agent| agent.perception_bias).collect();
+        assert!(biases.iter().all(|bias| bias.abs() <= 0.05));
+        assert!(biases.iter().any(|&bias| bias != 0.0));
+    }
+
+    #[test]
+    fn test_unknown_policy_gets_new_id() {
+        let config = SimulationConfig {
+            grid_size: 3,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            init_pattern: InitPattern::HalfAndHalf { left: 0, right: 1 },
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        let mut grid = sim.game.get_grid().clone();
+        grid[[0, 0]] = Agent::new(Arc::new(PredictFromYesterday));
+        sim.game.set_grid(grid);
+
+        let policy_ids = sim.policy_id_grid();
+        assert_eq!(policy_ids[[0, 0]], 2);
+        assert_eq!(policy_ids[[0, 2]], 1);
+        assert_eq!(sim.strategy_names(), vec!["Always Go", "Never Go", "Predict from yesterday"]);
+        // The id stays stable once assigned
+        assert_eq!(sim.initial_frame().policy_ids[[0, 0]], 2);
+        assert_eq!(sim.strategy_names().len(), 3);
+    }
+
+    #[test]
+    fn test_correlation_length() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let random = Array2::from_shape_fn((40, 40), |_| rng.gen_range(0..4) as StrategyId);
+        assert!(correlation_length(&random) < 1.5);
+
+        let solid = Array2::<StrategyId>::zeros((40, 40));
+        assert_eq!(correlation_length(&solid), 20.0);
+
+        // Two large blocks stay correlated over long distances
+        let halves = Array2::from_shape_fn((40, 40), |(_, c)| (c >= 20) as StrategyId);
+        assert!(correlation_length(&halves) > 10.0);
+    }
+
+    #[test]
+    fn test_init_from_csv() {
+        let path = std::env::temp_dir().join(format!("el_farol_grid_{}.csv", std::process::id()));
+        std::fs::write(&path, "0, 1, 1\n1,0,1\n\n0,0,2\n").unwrap();
+
+        let expected = Array2::from_shape_vec((3, 3), vec![0, 1, 1, 1, 0, 1, 0, 0, 2]).unwrap();
+        assert_eq!(read_policy_grid(&path).unwrap(), expected);
+
+        let config = SimulationConfig {
+            grid_size: 3,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo), Arc::new(RandomPolicy)],
+            init_pattern: InitPattern::FromFile(path.clone()),
+            ..Default::default()
+        };
+        assert_eq!(Simulation::new(config.clone()).policy_id_grid(), expected);
+
+        // Grids that do not fit the config are reported instead of panicking
+        let wrong_size = SimulationConfig { grid_size: 4, ..config.clone() };
+        assert!(matches!(Simulation::try_new(wrong_size), Err(ElFarolError::Config(_))));
+        let too_few_strategies = SimulationConfig { initial_strategies: vec![Arc::new(AlwaysGo)], ..config.clone() };
+        assert!(matches!(Simulation::try_new(too_few_strategies), Err(ElFarolError::Config(_))));
+
+        std::fs::write(&path, "0,1\n1").unwrap();
+        assert!(read_policy_grid(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(Simulation::try_new(config), Err(ElFarolError::Io(_))));
+    }
+
+    #[test]
+    fn test_perturb_changes_half_the_cells() {
+        let config = SimulationConfig {
+            grid_size: 10,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            init_pattern: InitPattern::HalfAndHalf { left: 0, right: 0 },
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        let mut rng = StdRng::seed_from_u64(9);
+        assert_eq!(sim.perturb(0.5, 1, &mut rng), 50);
+        assert_eq!(sim.policy_id_grid().iter().filter(|&&id| id == 1).count(), 50);
+    }
+
+    #[test]
+    fn test_frozen_grid_does_not_recover_from_perturbation() {
+        let config = SimulationConfig {
+            grid_size: 6,
+            rounds_per_update: 2,
+            policy_retention_rate: 1.0,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            init_pattern: InitPattern::HalfAndHalf { left: 0, right: 1 },
+            seed: Some(4),
+            ..Default::default()
+        };
+        let experiment = PerturbationExperiment {
+            stable_iterations: 2,
+            max_iterations: 10,
+            fraction: 0.0,
+            recovery_iterations: 3,
+            ..Default::default()
+        };
+        let outcome = run_perturbation_experiment(config.clone(), &experiment);
+        assert!(outcome.reached_equilibrium);
+        assert!(outcome.returned);
+        assert_eq!(outcome.before, outcome.after);
+
+        // Nobody ever switches back, so flipping everything to "Always Go" sticks
+        let outcome = run_perturbation_experiment(config, &PerturbationExperiment { fraction: 1.0, ..experiment });
+        assert!(!outcome.returned);
+        assert!(outcome.after.iter().all(|&id| id == 0));
+    }
+
+    #[test]
+    fn test_warmup_builds_history_without_adapting() {
+        let config = SimulationConfig {
+            grid_size: 5,
+            policy_retention_rate: 0.0,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo), Arc::new(PredictFromYesterday)],
+            seed: Some(11),
+            ..Default::default()
+        };
+        let cold = Simulation::new(config.clone());
+        let warm = Simulation::new(SimulationConfig { warmup_rounds: 7, ..config });
+
+        assert_eq!(warm.game.history().len(), 7);
+        assert_eq!(warm.policy_id_grid(), cold.policy_id_grid());
+        assert!(warm.game.get_grid().iter().all(|agent| agent.performance_history().is_empty()));
+        assert_eq!(warm.iteration(), 0);
+    }
+
+    #[test]
+    fn test_decision_churn() {
+        let config = SimulationConfig {
+            grid_size: 4,
+            rounds_per_update: 5,
+            initial_strategies: vec![Arc::new(AlwaysGo)],
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        for _ in 0..3 {
+            sim.run_iteration();
+        }
+        assert_eq!(sim.get_statistics()["decision_churn"], vec![0.0; 3]);
+    }
+
+    #[test]
+    fn test_frames_iterator_stops_after_num_iterations() {
+        let config = SimulationConfig {
+            grid_size: 3,
+            num_iterations: 4,
+            rounds_per_update: 1,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        assert_eq!(sim.frames().take(1).count(), 1);
+
+        let mut frames = sim.frames();
+        assert_eq!(frames.by_ref().count(), 3);
+        assert!(frames.next().is_none());
+        assert!(frames.next().is_none());
+        assert_eq!(sim.iteration(), 4);
+    }
+
+    #[test]
+    fn test_grid_stability_stops_frozen_run() {
+        let config = SimulationConfig {
+            grid_size: 4,
+            rounds_per_update: 1,
+            initial_strategies: vec![Arc::new(AlwaysGo)],
+            stop_when_frozen: Some(2),
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        sim.run_iteration();
+        assert!(!sim.should_stop());
+        sim.run_iteration();
+        assert_eq!(sim.get_statistics()["grid_stability"], vec![1.0, 1.0]);
+        assert!(sim.should_stop());
+    }
+
+    #[test]
+    fn test_disabled_statistics_are_skipped() {
+        let config = SimulationConfig {
+            grid_size: 4,
+            rounds_per_update: 2,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            stats_to_collect: [StatKind::ThresholdDeviation, StatKind::RegretFraction].into(),
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config.clone());
+        sim.run_iteration();
+        let mut keys: Vec<&str> = sim.get_statistics().keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["regret_fraction", "threshold_deviation"]);
+
+        // Early stopping still gets the strategy count it needs
+        let mut sim = Simulation::new(SimulationConfig { stop_on_monoculture: true, ..config });
+        sim.run_iteration();
+        assert!(sim.get_statistics().contains_key("unique_strategies"));
+        assert!(!sim.get_statistics().contains_key("correlation_length"));
+    }
+
+    #[test]
+    fn test_golden_seeded_run() {
+        // Only deterministic policies, so the seeded RNG is the sole source of randomness. If this
+        // fails after an intentional change to adaptation, update the expected grid.
+        let config = SimulationConfig {
+            grid_size: 6,
+            rounds_per_update: 3,
+            initial_strategies: vec![
+                Arc::new(AlwaysGo),
+                Arc::new(NeverGo),
+                Arc::new(ConstantPolicy::new(0.3)),
+                Arc::new(TargetThresholdPolicy::with_threshold(0.7, 0.0)),
+            ],
+            temperature: 0.5,
+            policy_retention_rate: 0.3,
+            seed: Some(2024),
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        let frames: Vec<Frame> = (0..20).map(|_| sim.run_iteration()).collect();
+
+        let expected = ndarray::arr2(&[
+            [0, 0, 2, 2, 2, 2],
+            [2, 0, 2, 2, 2, 2],
+            [0, 0, 1, 1, 2, 2],
+            [2, 1, 1, 1, 1, 2],
+            [2, 2, 1, 1, 1, 1],
+            [2, 1, 1, 1, 1, 1],
+        ]);
+        assert_eq!(frames.last().unwrap().policy_ids, expected);
+        assert_eq!(frames.last().unwrap().attendance_ratio, 26.0 / 36.0);
+    }
+
+    #[test]
+    fn test_target_attendance_init() {
+        let config = SimulationConfig {
+            grid_size: 10,
+            rounds_per_update: 1,
+            initial_strategies: vec![
+                Arc::new(AlwaysGo),
+                Arc::new(NeverGo),
+                Arc::new(ConstantPolicy::new(0.2)),
+                Arc::new(ConstantPolicy::new(0.9)),
+            ],
+            init_pattern: InitPattern::TargetAttendance(0.3),
+            seed: Some(3),
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        let frame = sim.run_iteration();
+        assert!((frame.attendance_ratio - 0.3).abs() < 0.01);
+        assert_eq!(sim.game.history, vec![frame.attendance_ratio]);
+    }
+
+    #[test]
+    #[should_panic(expected = "fixed prediction")]
+    fn test_target_attendance_rejects_history_based_strategies() {
+        let config = SimulationConfig {
+            grid_size: 4,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(PredictFromYesterday)],
+            init_pattern: InitPattern::TargetAttendance(0.5),
+            seed: Some(3),
+            ..Default::default()
+        };
+        Simulation::new(config);
+    }
+
+    #[test]
+    fn test_batch_attendance() {
+        let config = SimulationConfig {
+            grid_size: 4,
+            rounds_per_update: 4,
+            initial_strategies: vec![Arc::new(AlwaysGo), Arc::new(NeverGo)],
+            record_every_round: true,
+            record_batch_attendance: true,
+            seed: Some(1),
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        let frame = sim.run_iteration();
+        let batch = frame.batch_attendance.unwrap();
+        assert_eq!(batch.len(), 4);
+        assert_eq!(*batch.last().unwrap(), frame.attendance_ratio);
+        // The per-round frames of the batch carry the same ratios but no batch of their own
+        let round_frames = sim.take_round_frames();
+        assert!(round_frames.iter().all(|frame| frame.batch_attendance.is_none()));
+        let round_ratios: Vec<f64> = round_frames.iter().map(|frame| frame.attendance_ratio).collect();
+        assert_eq!(round_ratios, batch);
+
+        sim.config.record_batch_attendance = false;
+        assert!(sim.run_iteration().batch_attendance.is_none());
+    }
+
+    #[test]
+    fn test_random_sequential_scores_from_snapshot() {
+        // Every cell has its own policy and a distinct score, so greedy copying is deterministic.
+        // In sequence, an agent copies the current policy of its best-scoring neighbor, even one
+        // that already switched this sweep, scored by what its cell earned in the last batch.
+        let size = 5;
+        let config = SimulationConfig {
+            grid_size: size,
+            initial_strategies: vec![Arc::new(AlwaysGo)],
+            temperature: 0.0,
+            policy_retention_rate: 0.0,
+            seed: Some(6),
+            update_order: UpdateOrder::RandomSequential,
+            ..Default::default()
+        };
+        let mut sim = Simulation::new(config);
+        let score = |[i, j]: [usize; 2]| ((i * size + j) * 7 % (size * size)) as f64 + 1.0;
+        let value = |[i, j]: [usize; 2]| (i * size + j) as f64 / (size * size) as f64;
+        let grid = Array2::from_shape_fn((size, size), |(i, j)| {
+            let mut agent = Agent::new(Arc::new(ConstantPolicy::new(value([i, j]))));
+            agent.set_performance_history(vec![score([i, j])]);
+            agent
+        });
+        sim.game.set_grid(grid);
+
+        let mut order: Vec<[usize; 2]> = (0..size).flat_map(|i| (0..size).map(move |j| [i, j])).collect();
+        order.shuffle(&mut sim.rng.clone());
+        let mut expected = Array2::from_shape_fn((size, size), |(i, j)| value([i, j]));
+        for &[i, j] in &order {
+            let best = neighbor_positions(&sim.config, i, j)
+                .into_iter()
+                .max_by(|&a, &b| score(a).total_cmp(&score(b)))
+                .unwrap();
+            expected[[i, j]] = expected[best];
+        }
+
+        sim.adapt_strategies();
+        let adopted = sim.game.get_grid().map(|agent| fixed_prediction(agent.current_policy().as_ref()).unwrap());
+        assert_eq!(adopted, expected);
+    }
+
+    #[test]
+    fn test_validate_performance_discount() {
+        let with_discount = |performance_discount| SimulationConfig { performance_discount, ..Default::default() };
+        assert!(with_discount(1.0).validate().is_ok());
+        assert!(with_discount(0.5).validate().is_ok());
+        for discount in [0.0, -0.5, 1.5, f64::NAN] {
+            assert!(matches!(with_discount(discount).validate(), Err(ElFarolError::Config(_))));
+        }
+    }
+}